@@ -0,0 +1,28 @@
+use std::time::Instant;
+
+// Runs `f` and prints how long it took after `label`, padded so a run of
+// timings lines up as a table.
+pub fn time<T>(label: &str, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    println!("{:<40} {:>10.1?}", label, start.elapsed());
+    result
+}
+
+// A xorshift generator for making bench inputs. Nowhere near random enough
+// for anything else, but a fixed seed gives the same inputs on every run, so
+// timings can be compared. The seed must not be 0.
+pub struct XorShift(u64);
+
+impl XorShift {
+    pub fn new(seed: u64) -> Self {
+        XorShift(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+}
@@ -54,7 +54,7 @@ impl FloatingIsland {
                     if next_height == peak_height {
                         peaks.push(next_pos);
                     } else {
-                        queue.push(State { pos: next_pos, height: *next_height });
+                        queue.push(State { pos: next_pos, height: next_height });
                     }
                 }
             }
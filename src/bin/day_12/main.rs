@@ -1,5 +1,5 @@
 use std::collections::{HashMap, HashSet};
-use advent_of_code_2024::Vec2D;
+use advent_of_code_2024::{svg, Vec2D};
 
 #[derive(Debug)]
 struct Garden {
@@ -78,6 +78,16 @@ impl Garden {
         regions
     }
 
+    // Every region gets its own colour, so same-letter regions stay apart.
+    fn render(&self, regions: &[Region]) -> String {
+        let region_ids: HashMap<Vec2D, usize> = regions.iter().enumerate()
+            .flat_map(|(i, region)| region.plots.iter().map(move |plot| (*plot, i)))
+            .collect();
+        svg::grid(self.width, self.height, |x, y| {
+            region_ids.get(&Vec2D::new(x as i64, y as i64)).map(|i| svg::palette(*i))
+        })
+    }
+
     fn fence_price_by_perimeter(regions: &Vec<Region>) -> usize {
         regions.iter().map(|region| region.area() * region.perimeter()).sum()
     }
@@ -107,6 +117,10 @@ fn main() {
     let input = include_str!("input");
     let garden = Garden::from(input);
     let regions = garden.find_regions();
+    if std::env::args().nth(1).as_deref() == Some("render") {
+        print!("{}", garden.render(&regions));
+        return;
+    }
     println!("Part 1: {:?}", Garden::fence_price_by_perimeter(&regions));
     println!("Part 2: {:?}", Garden::fence_price_by_sides(&regions));
 }
\ No newline at end of file
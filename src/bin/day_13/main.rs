@@ -1,6 +1,6 @@
 use std::sync::LazyLock;
 use regex::Regex;
-use advent_of_code_2024::{Vec2D};
+use advent_of_code_2024::{lint, Vec2D};
 
 static RE_BUTTON: LazyLock<Regex> = LazyLock::new(
     || Regex::new(r"Button [A|B]: X\+(\d+), Y\+(\d+)").unwrap()
@@ -78,8 +78,35 @@ impl From<&str> for MachineBehavior {
     }
 }
 
+fn lint(input: &str) -> lint::Report {
+    let machines = input.split("\n\n").collect::<Vec<_>>();
+    let malformed = machines.iter().enumerate()
+        .filter(|(_, mb)| {
+            let mut lines = mb.lines();
+            !(lines.next().is_some_and(|line| RE_BUTTON.is_match(line))
+                && lines.next().is_some_and(|line| RE_BUTTON.is_match(line))
+                && lines.next().is_some_and(|line| RE_PRIZE.is_match(line)))
+        })
+        .map(|(i, _)| format!("machine {} does not parse", i + 1))
+        .collect::<Vec<_>>();
+    let mut report = lint::Report::new();
+    report.check("every machine has two buttons and a prize", malformed.clone());
+    if !malformed.is_empty() {
+        return report;
+    }
+    let Arcade(machine_behaviors) = Arcade::from(input);
+    report.check("buttons are not collinear, so presses are uniquely determined", machine_behaviors.iter().enumerate()
+        .filter(|(_, mb)| mb.button_a.x * mb.button_b.y == mb.button_b.x * mb.button_a.y)
+        .map(|(i, mb)| format!("machine {}: A {:?} and B {:?} point the same way", i + 1, mb.button_a, mb.button_b))
+        .collect());
+    report
+}
+
 fn main() {
     let input = include_str!("input");
+    if std::env::args().nth(1).as_deref() == Some("lint") {
+        lint(input).finish();
+    }
     let configs = Arcade::from(input);
     println!("part 1: {:?}", configs.min_tokens_required(None));
     println!("part 2: {:?}", configs.min_tokens_required(Some(|prize| {
@@ -1,7 +1,7 @@
 use itertools::Itertools;
 use std::sync::LazyLock;
 use regex::Regex;
-use advent_of_code_2024::Vec2D;
+use advent_of_code_2024::{svg, Vec2D};
 
 static RE_ROBOT: LazyLock<Regex> = LazyLock::new(
     || Regex::new(r"p=(-?\d+),(-?\d+) v=(-?\d+),(-?\d+)").unwrap()
@@ -58,6 +58,13 @@ impl EBHQ {
         quadrants.iter().product()
     }
 
+    fn render(&self, t: usize, bounds: Vec2D) -> String {
+        let robots = self.tick_n(t, bounds).iter().map(|r| r.p).counts();
+        svg::grid(bounds.x as usize, bounds.y as usize, |x, y| {
+            robots.get(&Vec2D::new(x as i64, y as i64)).map(|_| "#00cc00".to_string())
+        })
+    }
+
     fn find_xmas_tree(&mut self, bounds: Vec2D) -> usize {
         (0..).find_or_first(|i| {
             let robots = self.tick_n(*i, bounds);
@@ -78,6 +85,11 @@ fn main() {
     let input = include_str!("input");
     let mut ebhq = EBHQ::from(input);
     let bounds = Vec2D::new(101, 103);
+    if std::env::args().nth(1).as_deref() == Some("render") {
+        let t = ebhq.find_xmas_tree(bounds);
+        print!("{}", ebhq.render(t, bounds));
+        return;
+    }
     println!("Part 1: {}", ebhq.safety_factor(100, bounds));
     println!("Part 2: {}", ebhq.find_xmas_tree(bounds));
 }
\ No newline at end of file
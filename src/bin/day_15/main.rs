@@ -1,7 +1,8 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Display;
 use itertools::Itertools;
-use advent_of_code_2024::Vec2D;
+use advent_of_code_2024::{svg, Vec2D};
+use advent_of_code_2024::repl::{arg, Repl};
 
 type Pos = Vec2D;
 
@@ -14,11 +15,13 @@ enum Obj {
     BoxPosCollider(usize),
 }
 
+#[derive(Clone)]
 struct Box {
     pos: Pos,
     width: usize,
 }
 
+#[derive(Clone)]
 struct Warehouse {
     width: usize,
     height: usize,
@@ -188,10 +191,73 @@ impl Display for Warehouse {
     }
 }
 
+fn parse_dir(c: char) -> Result<Dir, String> {
+    match c {
+        '^' => Ok(Vec2D::up()),
+        '>' => Ok(Vec2D::right()),
+        'v' => Ok(Vec2D::down()),
+        '<' => Ok(Vec2D::left()),
+        c => Err(format!("invalid move: {}", c)),
+    }
+}
+
+// The repl state tracks how many of the input's moves have been run so far.
+fn repl(warehouse: Warehouse) {
+    Repl::new("day 15", (warehouse, 0usize))
+        .command("show", "", "print the warehouse", |(warehouse, next), _| {
+            Ok(format!("{}{}/{} moves run", warehouse, next, warehouse.moves.len()))
+        })
+        .command("step", "[n]", "run the next n moves from the input (default 1)", |(warehouse, next), args| {
+            let n = if args.is_empty() { 1 } else { arg(args, 0, "n")? };
+            let end = (*next + n).min(warehouse.moves.len());
+            for i in *next..end {
+                warehouse.run_move(warehouse.moves[i]);
+            }
+            *next = end;
+            Ok(format!("{}{}/{} moves run", warehouse, next, warehouse.moves.len()))
+        })
+        .command("run", "", "run all remaining moves from the input", |(warehouse, next), _| {
+            for i in *next..warehouse.moves.len() {
+                warehouse.run_move(warehouse.moves[i]);
+            }
+            *next = warehouse.moves.len();
+            Ok(format!("{}", warehouse))
+        })
+        .command("move", "<dirs>", "run ad-hoc moves, e.g. 'move ^^<'", |(warehouse, _), args| {
+            let dirs: String = arg(args, 0, "dirs")?;
+            for dir in dirs.chars().map(parse_dir).collect::<Result<Vec<_>, _>>()? {
+                warehouse.run_move(dir);
+            }
+            Ok(format!("{}", warehouse))
+        })
+        .command("widen", "", "replace the warehouse with its double-width version", |(warehouse, next), _| {
+            *warehouse = warehouse.wider_warehouse(2);
+            *next = 0;
+            Ok(format!("{}", warehouse))
+        })
+        .command("gps", "", "sum the GPS coordinates of all boxes", |(warehouse, _), _| {
+            Ok(warehouse.sum_gps_coordinates().to_string())
+        })
+        .run();
+}
 
 fn main() {
     let input = include_str!("input");
     let mut warehouse = Warehouse::from(input);
+    if std::env::args().nth(1).as_deref() == Some("repl") {
+        return repl(warehouse);
+    }
+    if std::env::args().nth(1).as_deref() == Some("render") {
+        let mut wider_warehouse = warehouse.wider_warehouse(2);
+        wider_warehouse.run_moves();
+        print!("{}", svg::text_grid(&wider_warehouse.to_string(), |c| match c {
+            '#' => Some("#666666".to_string()),
+            'O' => Some("#c08040".to_string()),
+            '@' => Some("#ffff66".to_string()),
+            _ => None,
+        }));
+        return;
+    }
     let mut wider_warehouse = warehouse.wider_warehouse(2);
     warehouse.run_moves();
     println!("Part 1: {}", warehouse.sum_gps_coordinates());
@@ -1,7 +1,7 @@
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::fmt::Display;
 
-use advent_of_code_2024::Vec2D;
+use advent_of_code_2024::{debug, svg, trace, Vec2D};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct Reindeer {
@@ -29,6 +29,7 @@ struct ReindeerMaze {
 struct Pathfind {
     lowest_score: usize,
     n_best_sitting_spots: usize,
+    best_sitting_spots: HashSet<Pos>,
 }
 
 impl ReindeerMaze {
@@ -45,8 +46,13 @@ impl ReindeerMaze {
         let mut move_to_score_map: HashMap<(Pos, Dir), usize> = HashMap::new();
         let mut curr_lowest_score = usize::MAX;
         let mut curr_lowest_path = HashSet::new();
+        let mut max_queue_size = queue.len();
+        let mut n_expanded = 0;
 
         while let Some(r) = queue.pop_front() {
+            n_expanded += 1;
+            max_queue_size = max_queue_size.max(queue.len() + 1);
+            trace!("queue size {}, at {:?} facing {:?} with score {}", queue.len(), r.pos, r.dir, r.score);
             for dir in [Dir::right(), Dir::up(), Dir::left(), Dir::down()].iter().cloned() {
                 let curr_pos = r.pos.clone() + dir;
                 let curr_score = r.score + self.calculate_move_score(&r, dir);
@@ -95,6 +101,8 @@ impl ReindeerMaze {
             }
         }
 
+        debug!("expanded {} states, max queue size {}, lowest score {}", n_expanded, max_queue_size, curr_lowest_score);
+
         let mut lowest_paths: HashSet<Vec2D> = HashSet::from_iter(
             curr_lowest_path.iter().map(|(p, _)| *p)
         );
@@ -108,9 +116,27 @@ impl ReindeerMaze {
         Pathfind {
             lowest_score: curr_lowest_score,
             n_best_sitting_spots: lowest_paths.len(),
+            best_sitting_spots: lowest_paths,
         }
     }
 
+    fn render(&self, best_sitting_spots: &HashSet<Pos>) -> String {
+        let width = self.map.keys().map(|pos| pos.x + 1).max().unwrap_or(0);
+        let height = self.map.keys().map(|pos| pos.y + 1).max().unwrap_or(0);
+        svg::grid(width as usize, height as usize, |x, y| {
+            let pos = Pos::new(x as i64, y as i64);
+            if pos == self.start || pos == self.end {
+                Some("#00cc00".to_string())
+            } else if best_sitting_spots.contains(&pos) {
+                Some("#ffff66".to_string())
+            } else if self.map.get(&pos) == Some(&MazeTile::Wall) {
+                Some("#666666".to_string())
+            } else {
+                None
+            }
+        })
+    }
+
     fn calculate_move_score(&self, r: &Reindeer, dir: Vec2D) -> usize {
         if r.dir == dir {
             1
@@ -158,8 +184,13 @@ fn main() {
     let mut maze = ReindeerMaze::from(input);
     let Pathfind {
         lowest_score,
-        n_best_sitting_spots
+        n_best_sitting_spots,
+        best_sitting_spots,
     } = maze.pathfind();
+    if std::env::args().nth(1).as_deref() == Some("render") {
+        print!("{}", maze.render(&best_sitting_spots));
+        return;
+    }
     println!("Part 1: {}", lowest_score);
     println!("Part 2: {}", n_best_sitting_spots);
 }
\ No newline at end of file
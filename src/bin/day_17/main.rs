@@ -1,7 +1,12 @@
-use std::ops::{BitXor};
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use std::collections::{HashSet, VecDeque};
 use std::sync::LazyLock;
 use itertools::Itertools;
 use regex::Regex;
+use advent_of_code_2024::bench::time;
+use advent_of_code_2024::repl::{arg, Repl};
+use advent_of_code_2024::{debug, trace};
 
 static RE_REGISTER: LazyLock<Regex> = LazyLock::new(
     || Regex::new(r"Register \w: (\d+)").unwrap()
@@ -9,24 +14,7 @@ static RE_REGISTER: LazyLock<Regex> = LazyLock::new(
 
 type Num = u64;
 
-trait Combo {
-    fn combo(&self, registers: &Registers) -> Self;
-}
-
-impl Combo for Num {
-    fn combo(&self, registers: &Registers) -> Self {
-        let n = *self;
-        match n {
-            0..=3 => n,
-            4 => registers.a,
-            5 => registers.b,
-            6 => registers.c,
-            _ => panic!("Invalid combo value: {}", n),
-        }
-    }
-}
-
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 enum Instr {
     Adv(Num),
     Bxl(Num),
@@ -52,63 +40,125 @@ struct Registers {
     c: Num,
 }
 
-enum Result {
-    Set(Register, Num),
-    Continue,
-    Jump(Num),
-    Output(Num),
-}
+const MNEMONICS: [&str; 8] = ["adv", "bxl", "bst", "jnz", "bxc", "out", "bdv", "cdv"];
 
 impl Instr {
-    fn run(&self, registers: &mut Registers) -> Result {
+    fn decode(opcode: Num, operand: Num) -> Option<Instr> {
+        Some(match opcode {
+            0 => Instr::Adv(operand),
+            1 => Instr::Bxl(operand),
+            2 => Instr::Bst(operand),
+            3 => Instr::Jnz(operand),
+            4 => Instr::Bxc(operand),
+            5 => Instr::Out(operand),
+            6 => Instr::Bdv(operand),
+            7 => Instr::Cdv(operand),
+            _ => return None,
+        })
+    }
+
+    fn opcode(&self) -> Num {
         match self {
-            Instr::Adv(n) => self.adv(*n, registers),
-            Instr::Bxl(n) => self.bxl(*n, registers),
-            Instr::Bst(n) => self.bst(*n, registers),
-            Instr::Jnz(n) => self.jnz(*n, registers),
-            Instr::Bxc(n) => self.bxc(*n, registers),
-            Instr::Out(n) => self.out(*n, registers),
-            Instr::Bdv(n) => self.bdv(*n, registers),
-            Instr::Cdv(n) => self.cdv(*n, registers),
+            Instr::Adv(_) => 0,
+            Instr::Bxl(_) => 1,
+            Instr::Bst(_) => 2,
+            Instr::Jnz(_) => 3,
+            Instr::Bxc(_) => 4,
+            Instr::Out(_) => 5,
+            Instr::Bdv(_) => 6,
+            Instr::Cdv(_) => 7,
         }
     }
 
-    fn adv(&self, n: Num, registers: &mut Registers) -> Result {
-        Result::Set(Register::A, registers.a / 2_u64.pow(n.combo(registers) as u32))
+    fn operand(&self) -> Num {
+        match self {
+            Instr::Adv(n) | Instr::Bxl(n) | Instr::Bst(n) | Instr::Jnz(n)
+            | Instr::Bxc(n) | Instr::Out(n) | Instr::Bdv(n) | Instr::Cdv(n) => *n,
+        }
     }
 
-    fn bxl(&self, n: Num, registers: &mut Registers) -> Result {
-        Result::Set(Register::B, registers.b.bitxor(n))
+    fn has_combo_operand(&self) -> bool {
+        !matches!(self, Instr::Bxl(_) | Instr::Jnz(_) | Instr::Bxc(_))
     }
 
-    fn bst(&self, n: Num, registers: &mut Registers) -> Result {
-        Result::Set(Register::B, n.combo(registers).rem_euclid(8))
+    // Parses one line of assembly, the inverse of `Display`. Combo operands
+    // take a register name or a number; `bxc` ignores its operand, so it's
+    // optional there.
+    fn assemble(line: &str) -> Result<Instr, String> {
+        let mut words = line.split_whitespace();
+        let mnemonic = words.next().ok_or("expected an instruction")?;
+        let opcode = MNEMONICS.iter().position(|m| m.eq_ignore_ascii_case(mnemonic))
+            .ok_or(format!("unknown instruction: {}", mnemonic))? as Num;
+        let operand = match words.next() {
+            Some(word) => word,
+            None if opcode == 4 => "0",
+            None => return Err(format!("{} needs an operand", mnemonic)),
+        };
+        if let Some(extra) = words.next() {
+            return Err(format!("unexpected {:?} after the operand", extra));
+        }
+        let instr = Instr::decode(opcode, 0).unwrap();
+        let operand = match operand.to_ascii_uppercase().as_str() {
+            "A" | "B" | "C" if !instr.has_combo_operand() => {
+                return Err(format!("{} takes a literal operand, not a register", mnemonic));
+            }
+            "A" => 4,
+            "B" => 5,
+            "C" => 6,
+            n => n.parse::<Num>().ok().filter(|&n| n < 8)
+                .ok_or(format!("invalid operand: {}", operand))?,
+        };
+        Ok(Instr::decode(opcode, operand).unwrap())
     }
+}
 
-    fn jnz(&self, n: Num, registers: &mut Registers) -> Result {
-        match registers.a {
-            0 => Result::Continue,
-            _ => Result::Jump(n),
+impl Display for Instr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mnemonic = MNEMONICS[self.opcode() as usize];
+        match (self.has_combo_operand(), self.operand()) {
+            (true, 4) => write!(f, "{} A", mnemonic),
+            (true, 5) => write!(f, "{} B", mnemonic),
+            (true, 6) => write!(f, "{} C", mnemonic),
+            (_, n) => write!(f, "{} {}", mnemonic, n),
         }
     }
+}
 
-    fn bxc(&self, _n: Num, registers: &mut Registers) -> Result {
-        Result::Set(Register::B, registers.b.bitxor(registers.c))
-    }
 
-    fn out(&self, n: Num, registers: &mut Registers) -> Result {
-        Result::Output(n.combo(registers).rem_euclid(8))
+impl Registers {
+    fn set(&mut self, register: &str, n: Num) -> Result<(), String> {
+        match register {
+            "a" | "A" => self.a = n,
+            "b" | "B" => self.b = n,
+            "c" | "C" => self.c = n,
+            _ => return Err(format!("unknown register: {}", register)),
+        }
+        Ok(())
     }
+}
 
-    fn bdv(&self, n: Num, registers: &mut Registers) -> Result {
-        Result::Set(Register::B, registers.a / 2_u64.pow(n.combo(registers) as u32))
+impl Registers {
+    fn combo(&self, n: Num) -> Num {
+        match n {
+            0..=3 => n,
+            4 => self.a,
+            5 => self.b,
+            6 => self.c,
+            _ => panic!("Invalid combo value: {}", n),
+        }
     }
 
-    fn cdv(&self, n: Num, registers: &mut Registers) -> Result {
-        Result::Set(Register::C, registers.a / 2_u64.pow(n.combo(registers) as u32))
+    // `adv`, `bdv` and `cdv` divide A by a power of two, which is a shift.
+    fn shifted_a(&self, n: Num) -> Num {
+        self.a.checked_shr(self.combo(n) as u32).unwrap_or(0)
     }
 }
 
+impl Display for Registers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "A={} B={} C={}", self.a, self.b, self.c)
+    }
+}
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct Computer {
@@ -116,61 +166,78 @@ struct Computer {
 }
 
 impl Computer {
-    fn run_instr(&mut self, instr: &Instr) -> Result {
-        instr.run(&mut self.registers)
-    }
-
-    fn run_program(&mut self, program: &Program) -> Vec<Num> {
-        let program = program.0.clone();
-        let mut i = 0;
-        let mut output = vec![];
-        while i < program.len() {
-            let instr = &program[i];
-            match self.run_instr(instr) {
-                Result::Set(Register::A, n) => self.registers.a = n,
-                Result::Set(Register::B, n) => self.registers.b = n,
-                Result::Set(Register::C, n) => self.registers.c = n,
-                Result::Continue => {}
-                Result::Jump(n) => {
-                    i = n as usize;
-                    continue;
+    // Executes the instruction at `ip`, returning the next instruction
+    // pointer.
+    fn step(&mut self, program: &Program, ip: usize, output: &mut Vec<Num>) -> usize {
+        let registers = &mut self.registers;
+        match program.0[ip] {
+            Instr::Adv(n) => registers.a = registers.shifted_a(n),
+            Instr::Bxl(n) => registers.b ^= n,
+            Instr::Bst(n) => registers.b = registers.combo(n) % 8,
+            Instr::Jnz(n) => {
+                if registers.a != 0 {
+                    return n as usize;
                 }
-                Result::Output(n) => output.push(n)
             }
-            i += 1;
+            Instr::Bxc(_) => registers.b ^= registers.c,
+            Instr::Out(n) => output.push(registers.combo(n) % 8),
+            Instr::Bdv(n) => registers.b = registers.shifted_a(n),
+            Instr::Cdv(n) => registers.c = registers.shifted_a(n),
         }
-        output
+        ip + 1
     }
 
-    fn find_magic_register_a_value(&self, program: &Program) -> Num {
-        // definitely not the "right" way to do this...
-        let prog = program.1.clone();
-        let mut curr = 0u64;
-        for i in (1..prog.len()).rev() {
-            let step = 8_u64.pow(i as u32);
-            for j in (curr..).step_by(step as usize) {
-                let mut cloned = self.clone();
-                cloned.registers.a = j;
-                let output = cloned.run_program(program);
-                if output.len() != prog.len() {
-                    continue;
-                }
-                if output[i] == prog[i] {
-                    curr = j;
-                    break;
+    // Runs `program` to the end, replacing the contents of `output` with what
+    // it prints. Given a `target`, gives up as soon as the output stops being
+    // a prefix of it, and returns whether the output was exactly the target.
+    fn run_into(&mut self, program: &Program, output: &mut Vec<Num>, target: Option<&[Num]>) -> bool {
+        output.clear();
+        let mut ip = 0;
+        while ip < program.0.len() {
+            trace!("ip {}: {:?} {:?}", ip, program.0[ip], self.registers);
+            let printed = output.len();
+            ip = self.step(program, ip, output);
+            if let Some(target) = target {
+                if output.len() > printed && target.get(printed) != Some(&output[printed]) {
+                    return false;
                 }
             }
         }
+        target.is_none_or(|target| output.len() == target.len())
+    }
 
-        for i in curr.. {
-            let mut cloned = self.clone();
-            cloned.registers.a = i;
-            let output = cloned.run_program(program);
-            if output == prog {
-                return i;
+    fn run_program(&mut self, program: &Program) -> Vec<Num> {
+        let mut output = vec![];
+        self.run_into(program, &mut output, None);
+        output
+    }
+
+    // Finds the smallest register A that makes `program` print itself. Each
+    // pass of the loop prints one value and shifts A right by three bits, so
+    // A is rebuilt one octal digit at a time starting from the last value
+    // printed, backtracking when no digit reproduces the rest of the program.
+    fn find_quine_a(&self, program: &Program) -> Result<Num, String> {
+        program.check_quine_shape()?;
+        let target = &program.1;
+        let mut computer = self.clone();
+        let mut output = Vec::with_capacity(target.len());
+        let mut stack = vec![(target.len(), 0)];
+        while let Some((matched, a)) = stack.pop() {
+            if matched == 0 {
+                return Ok(a);
+            }
+            // pushed in reverse so the smallest digit is tried first, which
+            // makes the first complete solution the minimum
+            for digit in (0..8).rev() {
+                let candidate = a * 8 + digit;
+                computer.registers = Registers { a: candidate, ..self.registers.clone() };
+                if computer.run_into(program, &mut output, Some(&target[matched - 1..])) {
+                    debug!("{} of {} values match with a = {:o}", target.len() - matched + 1, target.len(), candidate);
+                    stack.push((matched - 1, candidate));
+                }
             }
         }
-        return 0;
+        Err(format!("no solution: no register A makes the program print {}", target.iter().join(",")))
     }
 }
 
@@ -195,24 +262,569 @@ impl From<&str> for Program {
     fn from(value: &str) -> Self {
         let split = value.split_whitespace().collect_vec();
         let raw = split[1].split(",").map(|s| s.parse::<Num>().unwrap()).collect_vec();
-        let chunks = split[1].split(',').chunks(2);
-        Program(chunks.into_iter().map(|chunk| {
-            let instr = chunk.collect_vec();
-            match instr[0] {
-                "0" => Instr::Adv(instr[1].parse().unwrap()),
-                "1" => Instr::Bxl(instr[1].parse().unwrap()),
-                "2" => Instr::Bst(instr[1].parse().unwrap()),
-                "3" => Instr::Jnz(instr[1].parse().unwrap()),
-                "4" => Instr::Bxc(instr[1].parse().unwrap()),
-                "5" => Instr::Out(instr[1].parse().unwrap()),
-                "6" => Instr::Bdv(instr[1].parse().unwrap()),
-                "7" => Instr::Cdv(instr[1].parse().unwrap()),
-                _ => panic!("Invalid instruction: {}", instr[0]),
-            }
+        Program(raw.chunks(2).map(|chunk| {
+            Instr::decode(chunk[0], chunk[1]).unwrap_or_else(|| panic!("Invalid instruction: {}", chunk[0]))
         }).collect(), raw)
     }
 }
 
+// The registers an instruction reads and writes, as (reads, writes).
+fn register_usage(instr: &Instr) -> (Vec<char>, Vec<char>) {
+    let combo = match instr.operand() {
+        4 if instr.has_combo_operand() => vec!['A'],
+        5 if instr.has_combo_operand() => vec!['B'],
+        6 if instr.has_combo_operand() => vec!['C'],
+        _ => vec![],
+    };
+    let reads_a_and_combo = [vec!['A'], combo.clone()].concat();
+    match instr {
+        Instr::Adv(_) => (reads_a_and_combo, vec!['A']),
+        Instr::Bxl(_) => (vec!['B'], vec!['B']),
+        Instr::Bst(_) => (combo, vec!['B']),
+        Instr::Jnz(_) => (vec!['A'], vec![]),
+        Instr::Bxc(_) => (vec!['B', 'C'], vec!['B']),
+        Instr::Out(_) => (combo, vec![]),
+        Instr::Bdv(_) => (reads_a_and_combo, vec!['B']),
+        Instr::Cdv(_) => (reads_a_and_combo, vec!['C']),
+    }
+}
+
+impl Program {
+    // The quine search assumes the program is a single loop that prints one
+    // value per pass, shifts A right by three bits, and recomputes B and C
+    // from A each time round. Says which part doesn't hold if not.
+    fn check_quine_shape(&self) -> Result<(), String> {
+        let instrs = &self.0;
+        let count = |f: fn(&Instr) -> bool| instrs.iter().filter(|instr| f(instr)).count();
+        if instrs.last() != Some(&Instr::Jnz(0)) {
+            return Err("the program must end with `jnz 0` to loop back to the start".to_string());
+        }
+        if count(|instr| matches!(instr, Instr::Jnz(_))) != 1 {
+            return Err("the program must have no jumps besides the final `jnz 0`".to_string());
+        }
+        if count(|instr| matches!(instr, Instr::Out(_))) != 1 {
+            return Err("the program must print exactly one value per loop".to_string());
+        }
+        if count(|instr| matches!(instr, Instr::Adv(_))) != 1 || !instrs.contains(&Instr::Adv(3)) {
+            return Err("the program must shift A right by three bits (`adv 3`) exactly once per loop".to_string());
+        }
+        let mut written = HashSet::new();
+        for (ip, instr) in instrs.iter().enumerate() {
+            let (reads, writes) = register_usage(instr);
+            if let Some(register) = reads.iter().find(|&&r| r != 'A' && !written.contains(&r)) {
+                return Err(format!(
+                    "`{}` at {} reads {} before the loop sets it, so the output depends on more than A", instr, ip, register,
+                ));
+            }
+            written.extend(writes);
+        }
+        Ok(())
+    }
+
+    fn from_instrs(instrs: Vec<Instr>) -> Program {
+        let raw = instrs.iter().flat_map(|instr| [instr.opcode(), instr.operand()]).collect();
+        Program(instrs, raw)
+    }
+
+    // One instruction per line, each commented with its address so jump
+    // targets are easy to follow.
+    fn disassemble(&self) -> String {
+        self.0.iter().enumerate()
+            .map(|(i, instr)| format!("{:<8} ; {}\n", instr.to_string(), i))
+            .collect()
+    }
+
+    // Reads back what `disassemble` prints. Blank lines and `;` comments are
+    // skipped.
+    fn assemble(source: &str) -> Result<Program, String> {
+        let instrs = source.lines().enumerate()
+            .map(|(i, line)| (i, line.split(';').next().unwrap().trim()))
+            .filter(|(_, line)| !line.is_empty())
+            .map(|(i, line)| Instr::assemble(line).map_err(|err| format!("line {}: {}", i + 1, err)))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Program::from_instrs(instrs))
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct TraceEntry {
+    ip: usize,
+    instr: Instr,
+    before: Registers,
+    after: Registers,
+}
+
+impl Display for TraceEntry {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>3}: {:<8} {} -> {}", self.ip, self.instr.to_string(), self.before, self.after)
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Stop {
+    Halted,
+    Breakpoint(usize),
+    OutputCount(usize),
+    StepLimit(usize),
+}
+
+// Programs that never clear register A loop forever, so `run` gives up after
+// this many steps unless told otherwise.
+const DEFAULT_STEP_LIMIT: usize = 100_000;
+
+// Only the most recent instructions are kept for `trace`, so a long or
+// looping run doesn't grow it without bound.
+const TRACE_CAPACITY: usize = 1000;
+
+// Steps a program one instruction at a time, recording each one, and can run
+// until it reaches a breakpoint, has printed a given number of values, or
+// hits the step limit.
+#[derive(Clone, Debug)]
+struct Debugger {
+    computer: Computer,
+    program: Program,
+    ip: usize,
+    output: Vec<Num>,
+    steps: usize,
+    breakpoints: HashSet<usize>,
+    break_on_output: Option<usize>,
+    step_limit: usize,
+    trace: VecDeque<TraceEntry>,
+}
+
+impl Debugger {
+    fn new(computer: Computer, program: Program) -> Self {
+        Debugger {
+            computer,
+            program,
+            ip: 0,
+            output: vec![],
+            steps: 0,
+            breakpoints: HashSet::new(),
+            break_on_output: None,
+            step_limit: DEFAULT_STEP_LIMIT,
+            trace: VecDeque::new(),
+        }
+    }
+
+    fn registers(&self) -> &Registers {
+        &self.computer.registers
+    }
+
+    fn registers_mut(&mut self) -> &mut Registers {
+        &mut self.computer.registers
+    }
+
+    fn halted(&self) -> bool {
+        self.ip >= self.program.0.len()
+    }
+
+    fn step(&mut self) -> Option<&TraceEntry> {
+        if self.halted() {
+            return None;
+        }
+        let before = self.computer.registers.clone();
+        let ip = self.ip;
+        self.ip = self.computer.step(&self.program, ip, &mut self.output);
+        self.steps += 1;
+        if self.trace.len() == TRACE_CAPACITY {
+            self.trace.pop_front();
+        }
+        self.trace.push_back(TraceEntry {
+            ip,
+            instr: self.program.0[ip],
+            before,
+            after: self.computer.registers.clone(),
+        });
+        self.trace.back()
+    }
+
+    // Always executes at least one instruction, so continuing from a
+    // breakpoint moves past it.
+    fn run(&mut self) -> Stop {
+        let start = self.steps;
+        loop {
+            if self.halted() {
+                return Stop::Halted;
+            }
+            if self.steps > start && self.breakpoints.contains(&self.ip) {
+                return Stop::Breakpoint(self.ip);
+            }
+            if self.steps - start >= self.step_limit {
+                return Stop::StepLimit(self.step_limit);
+            }
+            let printed = self.output.len();
+            self.step();
+            if self.output.len() > printed && self.break_on_output == Some(self.output.len()) {
+                return Stop::OutputCount(self.output.len());
+            }
+        }
+    }
+
+    fn status(&self) -> String {
+        let next = self.program.0.get(self.ip).map_or("halted".to_string(), |instr| instr.to_string());
+        format!("ip {} ({}) after {} steps, {}, output [{}]", self.ip, next, self.steps, self.registers(), self.output.iter().join(","))
+    }
+}
+
+fn debug(computer: Computer, program: Program) {
+    Repl::new("debug", Debugger::new(computer, program))
+        .command("status", "", "show the instruction pointer, registers and output", |debugger, _| {
+            Ok(debugger.status())
+        })
+        .command("step", "[n]", "execute the next n instructions (default 1)", |debugger, args| {
+            let n: usize = if args.is_empty() { 1 } else { arg(args, 0, "n")? };
+            let mut lines = vec![];
+            for _ in 0..n {
+                match debugger.step() {
+                    Some(entry) => lines.push(entry.to_string()),
+                    None => {
+                        lines.push("halted".to_string());
+                        break;
+                    }
+                }
+            }
+            Ok(lines.join("\n"))
+        })
+        .command("continue", "", "run until a breakpoint, the output count, the step limit or the end", |debugger, _| {
+            let stop = debugger.run();
+            Ok(format!("{:?}\n{}", stop, debugger.status()))
+        })
+        .command("break", "<ip>", "stop before executing the instruction at ip", |debugger, args| {
+            debugger.breakpoints.insert(arg(args, 0, "ip")?);
+            Ok(format!("breakpoints: {:?}", debugger.breakpoints.iter().sorted().collect_vec()))
+        })
+        .command("delete", "<ip>", "remove a breakpoint", |debugger, args| {
+            let ip: usize = arg(args, 0, "ip")?;
+            if !debugger.breakpoints.remove(&ip) {
+                return Err(format!("no breakpoint at {}", ip));
+            }
+            Ok(format!("breakpoints: {:?}", debugger.breakpoints.iter().sorted().collect_vec()))
+        })
+        .command("outputs", "<n>", "stop once the program has printed n values, 0 to clear", |debugger, args| {
+            let n: usize = arg(args, 0, "n")?;
+            debugger.break_on_output = (n > 0).then_some(n);
+            Ok(format!("{:?}", debugger.break_on_output))
+        })
+        .command("limit", "<steps>", "set how many steps `continue` may take", |debugger, args| {
+            debugger.step_limit = arg(args, 0, "steps")?;
+            Ok(debugger.step_limit.to_string())
+        })
+        .command("regs", "", "print the registers", |debugger, _| {
+            Ok(debugger.registers().to_string())
+        })
+        .command("set", "<a|b|c> <n>", "set a register", |debugger, args| {
+            let register: String = arg(args, 0, "register")?;
+            debugger.registers_mut().set(&register, arg(args, 1, "n")?)?;
+            Ok(debugger.registers().to_string())
+        })
+        .command("trace", "[n]", "show the last n executed instructions (default 10, at most 1000 are kept)", |debugger, args| {
+            let n: usize = if args.is_empty() { 10 } else { arg(args, 0, "n")? };
+            Ok(debugger.trace.iter().skip(debugger.trace.len().saturating_sub(n)).join("\n"))
+        })
+        .command("disasm", "", "print the program as assembly", |debugger, _| {
+            Ok(debugger.program.disassemble().trim_end().to_string())
+        })
+        .run();
+}
+
+// A register's value as an expression over the registers at the start of a
+// pass through the program.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum Expr {
+    Reg(Register),
+    Lit(Num),
+    Mod8(Box<Expr>),
+    Xor(Box<Expr>, Box<Expr>),
+    Shr(Box<Expr>, Box<Expr>),
+}
+
+// The constructors fold the easy cases so the formulas stay readable.
+impl Expr {
+    fn mod8(x: Expr) -> Expr {
+        match x {
+            Expr::Lit(n) => Expr::Lit(n % 8),
+            x if x.below_8() => x,
+            x => Expr::Mod8(Box::new(x)),
+        }
+    }
+
+    // Xor chains are flattened so their literals can be combined into one.
+    fn xor(l: Expr, r: Expr) -> Expr {
+        let mut terms = vec![];
+        let mut literal = 0;
+        let mut pending = vec![r, l];
+        while let Some(x) = pending.pop() {
+            match x {
+                Expr::Xor(l, r) => pending.extend([*r, *l]),
+                Expr::Lit(n) => literal ^= n,
+                x => terms.push(x),
+            }
+        }
+        if literal != 0 || terms.is_empty() {
+            terms.push(Expr::Lit(literal));
+        }
+        terms.into_iter().reduce(|l, r| Expr::Xor(Box::new(l), Box::new(r))).unwrap()
+    }
+
+    fn shr(l: Expr, r: Expr) -> Expr {
+        match (l, r) {
+            (Expr::Lit(m), Expr::Lit(n)) => Expr::Lit(m.checked_shr(n as u32).unwrap_or(0)),
+            (x, Expr::Lit(0)) => x,
+            (Expr::Shr(x, m), Expr::Lit(n)) if matches!(*m, Expr::Lit(_)) => {
+                let Expr::Lit(m) = *m else { unreachable!() };
+                Expr::shr(*x, Expr::Lit(m + n))
+            }
+            (l, r) => Expr::Shr(Box::new(l), Box::new(r)),
+        }
+    }
+
+    fn below_8(&self) -> bool {
+        match self {
+            Expr::Lit(n) => *n < 8,
+            Expr::Mod8(_) => true,
+            Expr::Xor(l, r) => l.below_8() && r.below_8(),
+            _ => false,
+        }
+    }
+
+    fn eval(&self, registers: &Registers) -> Num {
+        match self {
+            Expr::Reg(Register::A) => registers.a,
+            Expr::Reg(Register::B) => registers.b,
+            Expr::Reg(Register::C) => registers.c,
+            Expr::Lit(n) => *n,
+            Expr::Mod8(x) => x.eval(registers) % 8,
+            Expr::Xor(l, r) => l.eval(registers) ^ r.eval(registers),
+            Expr::Shr(l, r) => l.eval(registers).checked_shr(r.eval(registers) as u32).unwrap_or(0),
+        }
+    }
+
+    fn is_atom(&self) -> bool {
+        matches!(self, Expr::Reg(_) | Expr::Lit(_))
+    }
+
+    fn fmt_atom(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_atom() { write!(f, "{}", self) } else { write!(f, "({})", self) }
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Reg(register) => write!(f, "{:?}", register),
+            Expr::Lit(n) => write!(f, "{}", n),
+            Expr::Mod8(x) => {
+                x.fmt_atom(f)?;
+                write!(f, " % 8")
+            }
+            Expr::Xor(l, r) => {
+                // xor is associative, so chains don't need brackets
+                for (i, x) in [l, r].into_iter().enumerate() {
+                    if i > 0 {
+                        write!(f, " ^ ")?;
+                    }
+                    match x.as_ref() {
+                        Expr::Xor(..) | Expr::Mod8(_) => write!(f, "{}", x)?,
+                        x => x.fmt_atom(f)?,
+                    }
+                }
+                Ok(())
+            }
+            Expr::Shr(l, r) => {
+                l.fmt_atom(f)?;
+                write!(f, " >> ")?;
+                r.fmt_atom(f)
+            }
+        }
+    }
+}
+
+// What one pass through a program computes: the values it prints and the
+// registers it leaves behind, in terms of the registers it started with.
+#[derive(Clone, Debug)]
+struct Decompiled {
+    outputs: Vec<Expr>,
+    a: Expr,
+    b: Expr,
+    c: Expr,
+    loops: bool,
+}
+
+impl Decompiled {
+    fn combo(&self, n: Num) -> Expr {
+        match n {
+            0..=3 => Expr::Lit(n),
+            4 => self.a.clone(),
+            5 => self.b.clone(),
+            6 => self.c.clone(),
+            _ => Expr::Lit(n),
+        }
+    }
+
+    // Evaluates one pass, returning the printed values and the registers left
+    // behind.
+    fn eval(&self, registers: &Registers) -> (Vec<Num>, Registers) {
+        let outputs = self.outputs.iter().map(|out| out.eval(registers)).collect();
+        (outputs, Registers { a: self.a.eval(registers), b: self.b.eval(registers), c: self.c.eval(registers) })
+    }
+
+    // Whether each pass shifts A right, so a looping program is sure to stop.
+    fn terminates(&self) -> bool {
+        !self.loops || matches!(&self.a, Expr::Shr(a, n) if **a == Expr::Reg(Register::A) && matches!(**n, Expr::Lit(1..)))
+    }
+}
+
+impl Display for Decompiled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for out in self.outputs.iter() {
+            writeln!(f, "out = {}", out)?;
+        }
+        match &self.a {
+            Expr::Shr(a, n) if **a == Expr::Reg(Register::A) => writeln!(f, "A >>= {}", n)?,
+            a => writeln!(f, "A = {}", a)?,
+        }
+        if self.loops {
+            writeln!(f, "repeat while A != 0")?;
+        }
+        Ok(())
+    }
+}
+
+impl Program {
+    // Straight-line programs, optionally wrapped in a final `jnz 0`, can be
+    // executed symbolically.
+    fn decompile(&self) -> Result<Decompiled, String> {
+        let (body, loops) = match self.0.split_last() {
+            Some((Instr::Jnz(0), body)) => (body, true),
+            _ => (self.0.as_slice(), false),
+        };
+        let mut state = Decompiled {
+            outputs: vec![],
+            a: Expr::Reg(Register::A),
+            b: Expr::Reg(Register::B),
+            c: Expr::Reg(Register::C),
+            loops,
+        };
+        for (ip, instr) in body.iter().enumerate() {
+            if instr.has_combo_operand() && instr.operand() == 7 {
+                return Err(format!("`{}` at {} uses the reserved combo operand 7", instr, ip));
+            }
+            let combo = state.combo(instr.operand());
+            match instr {
+                Instr::Adv(_) => state.a = Expr::shr(state.a.clone(), combo),
+                Instr::Bxl(n) => state.b = Expr::xor(state.b.clone(), Expr::Lit(*n)),
+                Instr::Bst(_) => state.b = Expr::mod8(combo),
+                Instr::Jnz(_) => return Err(format!("`{}` at {}: only a final `jnz 0` is supported", instr, ip)),
+                Instr::Bxc(_) => state.b = Expr::xor(state.b.clone(), state.c.clone()),
+                Instr::Out(_) => state.outputs.push(Expr::mod8(combo)),
+                Instr::Bdv(_) => state.b = Expr::shr(state.a.clone(), combo),
+                Instr::Cdv(_) => state.c = Expr::shr(state.a.clone(), combo),
+            }
+        }
+        Ok(state)
+    }
+}
+
+// Checks a decompiled pass against the interpreter on the first `n` values of
+// register A, returning the first A they disagree on.
+fn check_decompiled(computer: &Computer, program: &Program, decompiled: &Decompiled, n: Num) -> Option<Num> {
+    (0..n).find(|&a| {
+        let mut registers = computer.registers.clone();
+        registers.a = a;
+        // the formula describes one pass, so unroll it until A runs out
+        let mut expected = vec![];
+        loop {
+            let (outputs, next) = decompiled.eval(&registers);
+            expected.extend(outputs);
+            registers = next;
+            if !decompiled.loops || registers.a == 0 {
+                break;
+            }
+        }
+        let mut cloned = computer.clone();
+        cloned.registers.a = a;
+        cloned.run_program(program) != expected
+    })
+}
+
+// Times the interpreter on the workloads the solvers put it through: plain
+// runs, checking candidates against the program the way the quine search
+// does, and the search itself.
+fn bench(computer: Computer, program: Program) {
+    const RUNS: Num = 1_000_000;
+    let target = &program.1;
+    let with_a = |a| Computer { registers: Registers { a, ..computer.registers.clone() } };
+
+    let allocating = time(&format!("{} runs, new output each", RUNS), || {
+        (0..RUNS).map(|a| with_a(a).run_program(&program).len()).sum::<usize>()
+    });
+    let mut output = vec![];
+    let reusing = time(&format!("{} runs, reused output", RUNS), || {
+        let mut runner = computer.clone();
+        (0..RUNS).map(|a| {
+            runner.registers = with_a(a).registers;
+            runner.run_into(&program, &mut output, None);
+            output.len()
+        }).sum::<usize>()
+    });
+    assert_eq!(allocating, reusing);
+
+    let full = time(&format!("{} checks, run to the end", RUNS), || {
+        (0..RUNS).filter(|&a| with_a(a).run_program(&program) == *target).count()
+    });
+    let early = time(&format!("{} checks, stop on divergence", RUNS), || {
+        let mut runner = computer.clone();
+        (0..RUNS).filter(|&a| {
+            runner.registers = with_a(a).registers;
+            runner.run_into(&program, &mut output, Some(target))
+        }).count()
+    });
+    assert_eq!(full, early);
+
+    time("quine search x 100", || {
+        for _ in 0..100 {
+            computer.find_quine_a(&program).ok();
+        }
+    });
+}
+
+fn repl(computer: Computer, program: Program) {
+    Repl::new("day 17", (computer, program))
+        .command("regs", "", "print the registers", |(computer, _), _| {
+            Ok(format!("{:?}", computer.registers))
+        })
+        .command("set", "<a|b|c> <n>", "set a register", |(computer, _), args| {
+            let register: String = arg(args, 0, "register")?;
+            computer.registers.set(&register, arg(args, 1, "n")?)?;
+            Ok(format!("{:?}", computer.registers))
+        })
+        .command("run", "[a]", "run the program, optionally with a different register A", |(computer, program), args| {
+            let mut cloned = computer.clone();
+            if !args.is_empty() {
+                cloned.registers.a = arg(args, 0, "a")?;
+            }
+            let output = cloned.run_program(program);
+            Ok(format!("{}\n{:?}", output.iter().join(","), cloned.registers))
+        })
+        .command("program", "", "print the raw program", |(_, program), _| {
+            Ok(program.1.iter().join(","))
+        })
+        .command("disasm", "", "print the program as assembly", |(_, program), _| {
+            Ok(program.disassemble().trim_end().to_string())
+        })
+        .command("asm", "<instr>...", "replace the program, with instructions separated by ';'", |(_, program), args| {
+            *program = Program::assemble(&args.join(" ").replace(';', "\n"))?;
+            Ok(program.1.iter().join(","))
+        })
+        .command("decompile", "", "print what one pass of the program computes", |(_, program), _| {
+            Ok(program.decompile()?.to_string().trim_end().to_string())
+        })
+        .command("part2", "", "search for the register A that makes the program print itself", |(computer, program), _| {
+            computer.find_quine_a(program).map(|a| a.to_string())
+        })
+        .run();
+}
 
 fn main() {
     let input = include_str!("input");
@@ -220,6 +832,101 @@ fn main() {
     let (mut computer, program) = (
         Computer::from(split.next().unwrap()), Program::from(split.next().unwrap())
     );
+    let args = std::env::args().collect_vec();
+    match args.get(1).map(String::as_str) {
+        Some("repl") => return repl(computer, program),
+        Some("bench") => return bench(computer, program),
+        Some("debug") => return debug(computer, program),
+        Some("disasm") => {
+            print!("{}", program.disassemble());
+            return;
+        }
+        Some("decompile") => {
+            match program.decompile() {
+                Ok(decompiled) => {
+                    print!("{}", decompiled);
+                    if !decompiled.terminates() {
+                        println!("not checked against the interpreter: A doesn't shrink each pass");
+                    } else if let Some(a) = check_decompiled(&computer, &program, &decompiled, 4096) {
+                        println!("differs from the interpreter for A = {}", a);
+                    } else {
+                        println!("matches the interpreter for A in 0..4096");
+                    }
+                }
+                Err(err) => println!("can't decompile: {}", err),
+            }
+            return;
+        }
+        Some("asm") => {
+            // assemble a file and run it with the input's registers
+            let Some(path) = args.get(2) else {
+                println!("usage: day_17 asm <file>");
+                return;
+            };
+            let program = std::fs::read_to_string(path).map_err(|err| err.to_string())
+                .and_then(|source| Program::assemble(&source));
+            match program {
+                Ok(program) => {
+                    println!("Program: {}", program.1.iter().join(","));
+                    println!("Output: {}", computer.run_program(&program).iter().join(","));
+                }
+                Err(err) => println!("{}: {}", path, err),
+            }
+            return;
+        }
+        _ => {}
+    }
     println!("Part 1: {}", computer.run_program(&program).iter().join(","));
-    println!("Part 2: {}", computer.find_magic_register_a_value(&program));
-}
\ No newline at end of file
+    match computer.find_quine_a(&program) {
+        Ok(a) => println!("Part 2: {}", a),
+        Err(err) => println!("Part 2: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn debugger(a: Num, program: &str) -> Debugger {
+        let computer = Computer { registers: Registers { a, b: 0, c: 0 } };
+        Debugger::new(computer, Program::from(format!("Program: {}", program).as_str()))
+    }
+
+    #[test]
+    fn runs_to_breakpoint() {
+        // adv 1; out A; jnz 0, with `ip` counting instructions
+        let mut debugger = debugger(729, "0,1,5,4,3,0");
+        debugger.breakpoints.insert(1);
+        assert_eq!(debugger.run(), Stop::Breakpoint(1));
+        assert_eq!(debugger.registers(), &Registers { a: 364, b: 0, c: 0 });
+        assert!(debugger.output.is_empty());
+
+        assert_eq!(debugger.run(), Stop::Breakpoint(1));
+        assert_eq!(debugger.registers(), &Registers { a: 182, b: 0, c: 0 });
+        assert_eq!(debugger.output, vec![4]);
+        assert_eq!(debugger.steps, 4);
+        assert_eq!(debugger.trace.back().map(|entry| entry.ip), Some(0));
+
+        debugger.breakpoints.clear();
+        assert_eq!(debugger.run(), Stop::Halted);
+        assert_eq!(debugger.output, vec![4, 6, 3, 5, 6, 3, 5, 2, 1, 0]);
+    }
+
+    #[test]
+    fn stops_on_output_count() {
+        let mut debugger = debugger(729, "0,1,5,4,3,0");
+        debugger.break_on_output = Some(3);
+        assert_eq!(debugger.run(), Stop::OutputCount(3));
+        assert_eq!(debugger.output, vec![4, 6, 3]);
+    }
+
+    #[test]
+    fn trace_is_capped_on_endless_programs() {
+        // out A; jnz 0, with A never changing
+        let mut debugger = debugger(1, "5,4,3,0");
+        debugger.step_limit = 5 * TRACE_CAPACITY;
+        assert_eq!(debugger.run(), Stop::StepLimit(5 * TRACE_CAPACITY));
+        assert_eq!(debugger.trace.len(), TRACE_CAPACITY);
+        assert_eq!(debugger.trace.back().map(|entry| entry.ip), Some(1));
+    }
+}
@@ -4,7 +4,7 @@ use std::fmt::{Display, Formatter};
 
 use itertools::Itertools;
 
-use advent_of_code_2024::Vec2D;
+use advent_of_code_2024::{debug, svg, trace, Vec2D};
 
 type Pos = Vec2D;
 
@@ -76,7 +76,7 @@ impl MemorySpace {
             for dir in [Pos::up(), Pos::left(), Pos::right(), Pos::down()].iter().cloned() {
                 let next_pos = v.clone() + dir;
                 if self.is_grid_pos(&next_pos) && self.grid.get(&next_pos) != Some(&Coord::Corrupted) {
-                    // println!("{:?}", dist);
+                    trace!("{:?} -> {:?}, heap size {}", v, next_pos, heap.len());
                     if *dist.get(&next_pos).unwrap() > dist.get(&v).unwrap() + 1 {
                         dist.insert(next_pos.clone(), dist.get(&v).unwrap() + 1);
                         heap.push(Reverse(Item { w: dist.get(&next_pos).unwrap().clone(), v: next_pos }));
@@ -98,6 +98,8 @@ impl MemorySpace {
             let mut cloned = self.clone();
             cloned.drop_bytes(&bytes_to_drop, mid);
             let x = cloned.get_steps_til_end();
+            debug!("lo {}, hi {}, dropping {} bytes -> {}", lo, hi, mid,
+                if x == usize::MAX { "blocked".to_string() } else { format!("{} steps", x) });
             if x == usize::MAX {
                 hi = mid - 1;
             } else {
@@ -108,6 +110,23 @@ impl MemorySpace {
     }
 }
 
+impl MemorySpace {
+    fn render(&self, highlight: Pos) -> String {
+        svg::grid(self.width, self.height, |x, y| {
+            let pos = Pos::new(x as i64, y as i64);
+            if pos == highlight {
+                Some("#ff3333".to_string())
+            } else if pos == self.start || pos == self.end {
+                Some("#00cc00".to_string())
+            } else if self.grid.get(&pos) == Some(&Coord::Corrupted) {
+                Some("#666666".to_string())
+            } else {
+                None
+            }
+        })
+    }
+}
+
 impl Display for MemorySpace {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         for y in 0..self.height {
@@ -143,6 +162,14 @@ fn main() {
         grid: HashMap::new(),
     };
     let cloned = memory_space.clone();
+    if std::env::args().nth(1).as_deref() == Some("render") {
+        let blocking_byte = cloned.find_blocking_byte(&bytes_to_drop);
+        let n = bytes_to_drop.iter().position(|pos| *pos == blocking_byte).unwrap();
+        let mut blocked = cloned.clone();
+        blocked.drop_bytes(&bytes_to_drop, n + 1);
+        print!("{}", blocked.render(blocking_byte));
+        return;
+    }
     memory_space.drop_bytes(&bytes_to_drop, 1024);
     println!("Part 1: {}", memory_space.get_steps_til_end());
     let blocking_byte = cloned.find_blocking_byte(&bytes_to_drop);
@@ -1,6 +1,6 @@
-use std::collections::{HashMap};
+use std::collections::{HashMap, HashSet};
 use std::ops::{RangeBounds};
-use advent_of_code_2024::Vec2D;
+use advent_of_code_2024::{lint, par, Vec2D};
 
 type Pos = Vec2D;
 
@@ -8,20 +8,24 @@ struct OrderedTrack(Vec<(Pos, usize)>);
 
 impl OrderedTrack {
     // This method is better for part 2 than it is for part 1 wrt efficiency, but hey, works for both :)
-    fn count_cheats<R: RangeBounds<usize>>(&self, skip_range: R, savings_threshold: usize) -> usize {
+    fn count_cheats<R: RangeBounds<usize> + Sync>(&self, skip_range: R, savings_threshold: usize) -> usize {
+        let starts = (0..self.0.len()).collect::<Vec<_>>();
+        par::map(&starts, |&i| self.count_cheats_from(i, &skip_range, savings_threshold)).iter().sum()
+    }
+
+    fn count_cheats_from<R: RangeBounds<usize>>(&self, i: usize, skip_range: &R, savings_threshold: usize) -> usize {
         let mut count = 0;
-        for (i, (pos, distance)) in self.0.iter().enumerate() {
-            for j in i + 1..self.0.len() {
-                let (pos_2, distance_2) = self.0[j];
-                let dx = pos.x.abs_diff(pos_2.x);
-                let dy = pos.y.abs_diff(pos_2.y);
-                let skip_length = (dx + dy) as usize;
-                if !skip_range.contains(&skip_length) {
-                    continue;
-                }
-                let cheat_savings = distance.saturating_sub(distance_2).saturating_sub(skip_length);
-                count += (cheat_savings >= savings_threshold) as usize;
+        let (pos, distance) = &self.0[i];
+        for j in i + 1..self.0.len() {
+            let (pos_2, distance_2) = self.0[j];
+            let dx = pos.x.abs_diff(pos_2.x);
+            let dy = pos.y.abs_diff(pos_2.y);
+            let skip_length = (dx + dy) as usize;
+            if !skip_range.contains(&skip_length) {
+                continue;
             }
+            let cheat_savings = distance.saturating_sub(distance_2).saturating_sub(skip_length);
+            count += (cheat_savings >= savings_threshold) as usize;
         }
         count
     }
@@ -100,8 +104,56 @@ impl From<&str> for RaceTrack {
     }
 }
 
+fn lint(input: &str) -> lint::Report {
+    let count = |c: char| input.matches(c).count();
+    let mut report = lint::Report::new();
+    report
+        .check("map only contains '#', '.', 'S' and 'E'", lint::unexpected_chars(input, "#.SE"))
+        .check("there is exactly one 'S' and one 'E'", [('S', count('S')), ('E', count('E'))].iter()
+            .filter(|(_, n)| *n != 1)
+            .map(|(c, n)| format!("found {} '{}'", n, c))
+            .collect());
+    if !report.is_ok() {
+        return report;
+    }
+
+    let track = RaceTrack::from(input);
+    let is_track = |pos: &Pos| matches!(track.map.get(pos), Some(MapItem::Track(_)));
+    let neighbours = |pos: &Pos| [Vec2D::up(), Vec2D::down(), Vec2D::left(), Vec2D::right()]
+        .into_iter()
+        .map(|dir| *pos + dir)
+        .filter(|next| is_track(next))
+        .collect::<Vec<_>>();
+    let mut branches = track.map.keys()
+        .filter(|pos| is_track(pos))
+        .filter_map(|pos| {
+            let expected = if *pos == track.start || *pos == track.end { 1 } else { 2 };
+            let n = neighbours(pos).len();
+            (n != expected).then(|| format!("{},{} has {} track neighbours, expected {}", pos.x, pos.y, n, expected))
+        })
+        .collect::<Vec<_>>();
+    branches.sort();
+    report.check("the track is a single path without branches or dead ends", branches);
+
+    let mut seen = HashSet::from([track.start]);
+    let mut queue = vec![track.start];
+    while let Some(pos) = queue.pop() {
+        queue.extend(neighbours(&pos).into_iter().filter(|next| seen.insert(*next)));
+    }
+    let n_track = track.map.keys().filter(|pos| is_track(pos)).count();
+    report.check("every track cell is reachable from 'S'", if seen.len() == n_track && seen.contains(&track.end) {
+        vec![]
+    } else {
+        vec![format!("{} of {} track cells reachable, 'E' reachable: {}", seen.len(), n_track, seen.contains(&track.end))]
+    });
+    report
+}
+
 fn main() {
     let input = include_str!("input");
+    if std::env::args().nth(1).as_deref() == Some("lint") {
+        lint(input).finish();
+    }
     let mut track: RaceTrack = input.into();
     let ordered_track = track.get_ordered_track();
     println!("Part 1: {}", ordered_track.count_cheats(2..=2, 100));
@@ -1,148 +1,316 @@
-use std::collections::{HashMap, HashSet, VecDeque};
-use std::iter::repeat;
-use std::io::{Read};
+use std::collections::{HashMap, VecDeque};
 use itertools::Itertools;
-use advent_of_code_2024::Vec2D;
+use advent_of_code_2024::{debug, Vec2D};
 
 type Pos = Vec2D;
+// press counts grow roughly 2.5x per robot, so u64 only lasts ~45 of them
+type Presses = u128;
+
+// Keypads are drawn as text grids, with '.' or ' ' marking a gap the arm
+// must never pass over.
+const NUMERIC_KEYPAD: &str = "789\n456\n123\n.0A";
+const DIRECTIONAL_KEYPAD: &str = ".^A\n<v>";
+
+const MOVES: [(char, Vec2D); 4] = [
+    ('^', Vec2D { x: 0, y: -1 }),
+    ('v', Vec2D { x: 0, y: 1 }),
+    ('<', Vec2D { x: -1, y: 0 }),
+    ('>', Vec2D { x: 1, y: 0 }),
+];
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct Keypad {
+    keys: HashMap<char, Pos>,
     grid: HashMap<Pos, char>,
-    curr: Pos,
+    // every shortest way of moving between two keys and pressing the second
+    paths: HashMap<(char, char), Vec<Vec<char>>>,
+}
+
+impl From<&str> for Keypad {
+    fn from(value: &str) -> Self {
+        let keys: HashMap<char, Pos> = value.lines().enumerate()
+            .flat_map(|(y, line)| line.chars().enumerate().map(move |(x, c)| (c, Pos::new(x as i64, y as i64))))
+            .filter(|(c, _)| *c != '.' && *c != ' ')
+            .collect();
+        let grid: HashMap<Pos, char> = keys.iter().map(|(&c, &pos)| (pos, c)).collect();
+        let paths = keys.keys()
+            .cartesian_product(keys.keys())
+            .map(|(&from, &to)| ((from, to), Keypad::shortest_paths(&grid, keys[&from], keys[&to])))
+            .collect();
+        Keypad { keys, grid, paths }
+    }
 }
 
 impl Keypad {
     fn numeric_keypad() -> Self {
-        Keypad {
-            grid: HashMap::from([
-                (Vec2D::new(0, 0), '7'),
-                (Vec2D::new(1, 0), '8'),
-                (Vec2D::new(2, 0), '9'),
-                (Vec2D::new(0, 1), '4'),
-                (Vec2D::new(1, 1), '5'),
-                (Vec2D::new(2, 1), '6'),
-                (Vec2D::new(0, 2), '1'),
-                (Vec2D::new(1, 2), '2'),
-                (Vec2D::new(2, 2), '3'),
-                (Vec2D::new(0, 3), ' '),
-                (Vec2D::new(1, 3), '0'),
-                (Vec2D::new(2, 3), 'A'),
-            ]),
-            curr: Vec2D::new(2, 3),
-        }
+        Keypad::from(NUMERIC_KEYPAD)
     }
 
     fn directional_keypad() -> Self {
-        Keypad {
-            grid: HashMap::from([
-                (Vec2D::new(0, 0), ' '),
-                (Vec2D::new(1, 0), '^'),
-                (Vec2D::new(2, 0), 'A'),
-                (Vec2D::new(0, 1), '<'),
-                (Vec2D::new(1, 1), 'v'),
-                (Vec2D::new(2, 1), '>'),
-            ]),
-            curr: Vec2D::new(2, 0),
-        }
-    }
-
-    fn pathfind(&mut self, to: char) -> Vec<char> {
-        // my first trial at this was a standard bfs search
-        // though this failed for part 2 a bit given I had more complex
-        // min path calculations with exploding combinations.
-        // This is meant to be a more efficient way to get the path
-        // Though I think I can take portions of this method back to the bfs...
-        let to_pos = *self.grid.iter().find(|(_, &c)| c == to).unwrap().0;
-        let curr = self.curr;
-        let diff = to_pos - curr;
-        let mut dx: Vec<char> = if diff.x > 0 {
-            repeat('>').take(diff.x as usize).collect()
-        } else {
-            repeat('<').take(-diff.x as usize).collect()
-        };
-        let mut dy: Vec<char> = if diff.y > 0 {
-            repeat('v').take(diff.y as usize).collect()
-        } else {
-            repeat('^').take(-diff.y as usize).collect()
-        };
-        let check_pos = Pos::new(curr.x, to_pos.y);
-        let check_pos_2 = Pos::new(to_pos.x, curr.y);
-        let (first, second) = if Some(&' ') == self.grid.get(&check_pos_2) {
-            (dy, dx)
-        } else if Some(&' ') == self.grid.get(&check_pos) {
-            (dx, dy)
-        } else if diff.x > 0 {
-            (dy, dx)
-        } else {
-            (dx, dy)
-        };
-        self.curr = to_pos;
-        first.into_iter()
-            .chain(second.into_iter())
-            .chain(vec!['A'].into_iter())
-            .collect()
+        Keypad::from(DIRECTIONAL_KEYPAD)
+    }
+
+    // Gaps only count inside the keypad's outline; anywhere else is off it.
+    fn is_gap(&self, pos: Pos) -> bool {
+        let (width, height) = (self.keys.values().map(|p| p.x).max(), self.keys.values().map(|p| p.y).max());
+        (0..=width.unwrap_or(-1)).contains(&pos.x) && (0..=height.unwrap_or(-1)).contains(&pos.y)
+    }
+
+    fn shortest_paths(grid: &HashMap<Pos, char>, from: Pos, to: Pos) -> Vec<Vec<char>> {
+        // distances back from `to`, so every step of a shortest path is one
+        // that gets closer
+        let mut distance = HashMap::from([(to, 0)]);
+        let mut queue = VecDeque::from([to]);
+        while let Some(pos) = queue.pop_front() {
+            for (_, dir) in MOVES {
+                let next = pos + dir;
+                if grid.contains_key(&next) && !distance.contains_key(&next) {
+                    distance.insert(next, distance[&pos] + 1);
+                    queue.push_back(next);
+                }
+            }
+        }
+        if !distance.contains_key(&from) {
+            return vec![];
+        }
+
+        let mut paths = vec![];
+        let mut stack = vec![(from, vec![])];
+        while let Some((pos, path)) = stack.pop() {
+            if pos == to {
+                paths.push([path, vec!['A']].concat());
+                continue;
+            }
+            for (c, dir) in MOVES {
+                let next = pos + dir;
+                if distance.get(&next) == Some(&(distance[&pos] - 1)) {
+                    stack.push((next, [path.clone(), vec![c]].concat()));
+                }
+            }
+        }
+        paths
+    }
+}
+
+// A door keypad followed by the keypads of the robots typing on it, the last
+// of which is pressed by a human.
+struct Chain {
+    keypads: Vec<Keypad>,
+    memo: HashMap<(char, char, usize), Presses>,
+}
+
+impl Chain {
+    fn new(door: Keypad, robot: Keypad, robots: usize) -> Result<Self, String> {
+        if robots > 0 {
+            let needed = MOVES.iter().map(|(c, _)| *c).chain(['A']).collect_vec();
+            let missing = needed.iter().filter(|c| !robot.keys.contains_key(c)).collect::<String>();
+            if !missing.is_empty() {
+                return Err(format!("the robots' keypad is missing {:?}", missing));
+            }
+            // the keypad above asks for any of these after any other
+            if let Some((from, to)) = needed.iter().cartesian_product(needed.iter())
+                .find(|(from, to)| robot.paths[&(**from, **to)].is_empty()) {
+                return Err(format!("the robots' keypad can't reach {:?} from {:?}", to, from));
+            }
+        }
+        let keypads = [vec![door], vec![robot; robots]].concat();
+        if let Some(keypad) = keypads.iter().find(|keypad| !keypad.keys.contains_key(&'A')) {
+            return Err(format!("keypad with keys {:?} has no 'A' to start from", keypad.keys.keys().sorted().join("")));
+        }
+        Ok(Chain { keypads, memo: HashMap::new() })
+    }
+
+    fn with_robots(robots: usize) -> Self {
+        Chain::new(Keypad::numeric_keypad(), Keypad::directional_keypad(), robots).unwrap()
+    }
+
+    // The fewest human presses needed to move the arm over keypad `depth`
+    // from `from` to `to` and press it. The keypads above it have all just
+    // pressed 'A', so each pair only needs working out once per depth.
+    fn cost(&mut self, from: char, to: char, depth: usize) -> Presses {
+        if depth == self.keypads.len() {
+            return 1;
+        }
+        if let Some(&cost) = self.memo.get(&(from, to, depth)) {
+            return cost;
+        }
+        let paths = self.keypads[depth].paths.get(&(from, to)).cloned()
+            .unwrap_or_else(|| panic!("no key {:?} or {:?} on keypad {}", from, to, depth));
+        let cost = paths.iter()
+            .map(|path| self.sequence_cost(path, depth + 1))
+            .min()
+            .unwrap_or_else(|| panic!("can't reach {:?} from {:?} on keypad {}", to, from, depth));
+        self.memo.insert((from, to, depth), cost);
+        cost
+    }
+
+    fn sequence_cost(&mut self, keys: &[char], depth: usize) -> Presses {
+        let mut prev = 'A';
+        let mut total = 0;
+        for &key in keys {
+            total = self.cost(prev, key, depth).checked_add(total)
+                .unwrap_or_else(|| panic!("press count overflows with {} keypads", self.keypads.len()));
+            prev = key;
+        }
+        total
+    }
+
+    // Whether every key of `code` is on the door keypad, and reachable from
+    // the one before it, starting from 'A'.
+    fn check_code(&self, code: &str) -> Result<(), String> {
+        let door = &self.keypads[0];
+        if let Some(key) = code.chars().find(|key| !door.keys.contains_key(key)) {
+            return Err(format!("{}: no key {:?} on the door keypad", code, key));
+        }
+        match ['A'].into_iter().chain(code.chars()).tuple_windows().find(|pair| door.paths[pair].is_empty()) {
+            Some((from, to)) => Err(format!("{}: can't reach {:?} from {:?} on the door keypad", code, to, from)),
+            None => Ok(()),
+        }
+    }
+
+    fn code_cost(&mut self, code: &str) -> Presses {
+        self.sequence_cost(&code.chars().collect_vec(), 0)
+    }
+
+    // Plays `presses` on the human's keypad through every robot, returning
+    // the code that reaches the door, or the first press that would point an
+    // arm at a gap or off its keypad.
+    fn replay(&self, presses: &str) -> Result<String, String> {
+        let mut arms = self.keypads.iter().map(|keypad| keypad.keys[&'A']).collect_vec();
+        let mut code = String::new();
+        for (i, press) in presses.chars().enumerate() {
+            let mut key = press;
+            for depth in (0..self.keypads.len()).rev() {
+                let keypad = &self.keypads[depth];
+                if key == 'A' {
+                    key = keypad.grid[&arms[depth]];
+                    if depth == 0 {
+                        code.push(key);
+                    }
+                    continue;
+                }
+                let (_, dir) = MOVES.iter().find(|(c, _)| *c == key)
+                    .ok_or(format!("press {} ({:?}): {:?} isn't a key the next robot understands", i + 1, press, key))?;
+                let next = arms[depth] + *dir;
+                if !keypad.grid.contains_key(&next) {
+                    let problem = if keypad.is_gap(next) { "at the gap" } else { "off the keypad" };
+                    let keypad = if depth == 0 { "door keypad".to_string() } else { format!("keypad {}", depth) };
+                    return Err(format!("press {} ({:?}): the arm over the {} points {} at {},{}", i + 1, press, keypad, problem, next.x, next.y));
+                }
+                arms[depth] = next;
+                break;
+            }
+        }
+        Ok(code)
+    }
+
+    // A cheapest way to make the move `cost` prices.
+    fn best_path(&mut self, from: char, to: char, depth: usize) -> Vec<char> {
+        let paths = self.keypads[depth].paths[&(from, to)].clone();
+        paths.into_iter().min_by_key(|path| self.sequence_cost(path, depth + 1)).unwrap()
+    }
+
+    // The keys typed on each keypad to enter `code`, from the door keypad
+    // down to the human's. Sequences grow exponentially with the chain, so
+    // this refuses anything over `max_presses`.
+    fn expand(&mut self, code: &str, max_presses: Presses) -> Result<Vec<String>, String> {
+        self.check_code(code)?;
+        let presses = self.code_cost(code);
+        if presses > max_presses {
+            return Err(format!("{} needs {} presses, too many to list", code, presses));
+        }
+        let mut layers = vec![code.chars().collect_vec()];
+        for depth in 0..self.keypads.len() {
+            let mut prev = 'A';
+            let mut next = vec![];
+            for &key in layers.last().unwrap() {
+                next.extend(self.best_path(prev, key, depth));
+                prev = key;
+            }
+            layers.push(next);
+        }
+        Ok(layers.into_iter().map(String::from_iter).collect())
     }
 }
 
+const MAX_LISTED_PRESSES: Presses = 100_000;
+
+fn numeric_part(code: &str) -> Presses {
+    code.chars().filter(char::is_ascii_digit).collect::<String>().parse().unwrap_or(0)
+}
+
+fn complexity(chain: &mut Chain, codes: &str) -> Result<Presses, String> {
+    codes.lines().map(|code| {
+        chain.check_code(code)?;
+        let cost = chain.code_cost(code);
+        debug!("{}: {} * {}", code, cost, numeric_part(code));
+        cost.checked_mul(numeric_part(code)).ok_or(format!("{}: complexity overflows", code))
+    }).sum()
+}
 
 fn main() {
     let input = include_str!("input");
-    let mut keypads = vec![
-        Keypad::numeric_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-        Keypad::directional_keypad(),
-    ];
-    let lines = input.lines();
-
-    let mut sum = 0;
-    for line in lines {
-        let mut codes = vec![line.chars().collect::<Vec<char>>()];
-        let mut counter = HashMap::new();
-        counter.insert(codes[0].clone(), 1);
-        for keypad in &mut keypads {
-            let mut next_counter = HashMap::new();
-
-            let mut ps = vec![];
-            for (code, count) in &counter {
-                for &c in code {
-                    let mut found_paths = keypad.pathfind(c).clone();
-                    ps.push(found_paths.clone());
-                    next_counter.entry(found_paths.clone())
-                        .and_modify(|v| *v += count)
-                        .or_insert(*count);
+    let args = std::env::args().collect_vec();
+    if args.get(1).map(String::as_str) == Some("chain") {
+        // try other chain lengths or keypad layouts
+        let usage = "usage: day_21 chain <robots> [door keypad file] [robot keypad file]";
+        let Some(robots) = args.get(2).and_then(|robots| robots.parse().ok()) else {
+            println!("{}", usage);
+            return;
+        };
+        let layout = |i: usize, default: &str| args.get(i).map_or(Ok(default.to_string()), |path| {
+            std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))
+        });
+        let chain = layout(3, NUMERIC_KEYPAD).and_then(|door| {
+            Chain::new(Keypad::from(door.as_str()), Keypad::from(layout(4, DIRECTIONAL_KEYPAD)?.as_str()), robots)
+        });
+        match chain.and_then(|mut chain| complexity(&mut chain, input)) {
+            Ok(sum) => println!("Sum: {}", sum),
+            Err(err) => println!("{}", err),
+        }
+        return;
+    }
+    if args.get(1).map(String::as_str) == Some("sequences") {
+        let Some(robots) = args.get(2).map_or(Some(2), |robots| robots.parse().ok()) else {
+            println!("usage: day_21 sequences [robots]");
+            return;
+        };
+        let mut chain = Chain::with_robots(robots);
+        for code in input.lines() {
+            match chain.expand(code, MAX_LISTED_PRESSES) {
+                Ok(layers) => {
+                    println!("{}", code);
+                    for (depth, layer) in layers.iter().enumerate().skip(1) {
+                        let typist = if depth == layers.len() - 1 { "human".to_string() } else { format!("robot {}", depth) };
+                        println!("  {:<9} {:>6}  {}", typist, layer.len(), layer);
+                    }
+                    match chain.replay(layers.last().unwrap()) {
+                        Ok(replayed) if replayed == code => println!("  replays to {}", replayed),
+                        Ok(replayed) => println!("  REPLAYS TO {}", replayed),
+                        Err(err) => println!("  REPLAY FAILED: {}", err),
+                    }
                 }
+                Err(err) => println!("{}", err),
             }
-
-            counter = next_counter.clone();
         }
-        // break;
-        let v: usize = line.chars().take(3).collect::<String>().parse().unwrap();
-        // println!("{} * {}", counter.iter().map(|(k, v)| k.len() * v).sum::<usize>(), v);
-        sum += v * counter.iter().map(|(k, v)| k.len() * v).sum::<usize>();
+        return;
     }
-    println!("Sum: {}", sum);
-}
\ No newline at end of file
+    if args.get(1).map(String::as_str) == Some("replay") {
+        let usage = "usage: day_21 replay <presses> [robots]";
+        let (Some(presses), Some(robots)) = (args.get(2), args.get(3).map_or(Some(2), |robots| robots.parse().ok())) else {
+            println!("{}", usage);
+            return;
+        };
+        match Chain::with_robots(robots).replay(presses) {
+            Ok(code) => println!("{}", code),
+            Err(err) => println!("{}", err),
+        }
+        return;
+    }
+    for (part, robots) in [(1, 2), (2, 25)] {
+        match complexity(&mut Chain::with_robots(robots), input) {
+            Ok(sum) => println!("Part {}: {}", part, sum),
+            Err(err) => println!("Part {}: {}", part, err),
+        }
+    }
+}
@@ -1,10 +1,12 @@
-use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::{BitXor, Rem};
+use std::sync::LazyLock;
 use itertools::Itertools;
-use advent_of_code_2024::Vec2D;
+use advent_of_code_2024::{par, Vec2D};
 
 type Pos = Vec2D;
 
+// Secrets live in 24 bits: an evolution only reads and keeps the low 24, so a
+// larger starting secret evolves exactly like its low bits would.
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct SecretNumber(u64);
 
@@ -22,6 +24,129 @@ impl SecretNumber {
     fn prune(n: u64) -> u64 {
         n.rem_euclid(16777216u64)
     }
+
+    // The secrets this one evolves into, starting with itself.
+    fn stream(&self) -> impl Iterator<Item = SecretNumber> {
+        std::iter::successors(Some(self.clone()), |secret_number| {
+            let mut next = secret_number.clone();
+            next.evolve();
+            Some(next)
+        })
+    }
+
+    // The secret `n` evolutions on, in O(log n) matrix applications. With
+    // n = 0 that's the secret itself, high bits and all.
+    fn jump(&self, n: u64) -> SecretNumber {
+        let secret = (0..64)
+            .filter(|bit| n >> bit & 1 == 1)
+            .fold(self.0, |secret, bit| EVOLVE_POWERS[bit].apply(secret));
+        SecretNumber(secret)
+    }
+
+    // The 24-bit secret that evolves into this one. Bits above the 24th are
+    // ignored, so this only undoes `evolve` for secrets under 2^24, which
+    // every evolved secret is.
+    fn previous(&self) -> SecretNumber {
+        SecretNumber(EVOLVE_INVERSE.apply(self.0))
+    }
+
+    fn price(&self) -> u64 {
+        self.0.rem_euclid(10)
+    }
+
+    // Evolves the secret `evolutions` times, returning the final secret and,
+    // for every window of four price changes, the price the first time that
+    // window appears.
+    fn first_prices(&self, evolutions: usize) -> (SecretNumber, Vec<(usize, u64)>) {
+        let mut secret_number = self.clone();
+        let mut seen = vec![0u64; WINDOWS.div_ceil(64)];
+        let mut first_prices = vec![];
+        let mut window = 0;
+        for (evolution, (before, after)) in self.stream().tuple_windows().take(evolutions).enumerate() {
+            // slide the window along by one change, dropping the oldest digit
+            window = (window * 19 + (after.price() + 9 - before.price()) as usize) % WINDOWS;
+            if evolution >= 3 && seen[window / 64] & (1 << (window % 64)) == 0 {
+                seen[window / 64] |= 1 << (window % 64);
+                first_prices.push((window, after.price()));
+            }
+            secret_number = after;
+        }
+        (secret_number, first_prices)
+    }
+
+    // Sums every buyer's first price for each window of changes, returning
+    // the sum of the final secrets and the per-window banana totals.
+    fn market(buyers: &[SecretNumber], evolutions: usize) -> (u64, Vec<u64>) {
+        let mut sum = 0;
+        let mut bananas = vec![0; WINDOWS];
+        for (secret_number, first_prices) in par::map(buyers, |buyer| buyer.first_prices(evolutions)) {
+            sum += secret_number.0;
+            for (window, price) in first_prices {
+                bananas[window] += price;
+            }
+        }
+        (sum, bananas)
+    }
+}
+
+const SECRET_BITS: usize = 24;
+
+// Mixing is xor and pruning keeps the low 24 bits, so an evolution is a linear
+// map on those bits over GF(2). Stored as the image of each bit.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct BitMatrix([u64; SECRET_BITS]);
+
+impl BitMatrix {
+    fn of(f: impl Fn(u64) -> u64) -> Self {
+        BitMatrix(std::array::from_fn(|bit| f(1 << bit)))
+    }
+
+    // Bits above the 24th are ignored, the same as an evolution does.
+    fn apply(&self, v: u64) -> u64 {
+        (0..SECRET_BITS).filter(|bit| v >> bit & 1 == 1).fold(0, |acc, bit| acc ^ self.0[bit])
+    }
+
+    // The map that applies `self` and then `next`.
+    fn then(&self, next: &BitMatrix) -> BitMatrix {
+        BitMatrix(self.0.map(|image| next.apply(image)))
+    }
+
+    // Gauss-Jordan elimination on (image, preimage) pairs, until each image
+    // is a single bit.
+    fn inverse(&self) -> Option<BitMatrix> {
+        let mut pairs: Vec<(u64, u64)> = (0..SECRET_BITS).map(|bit| (self.0[bit], 1 << bit)).collect();
+        for bit in 0..SECRET_BITS {
+            let pivot = (bit..SECRET_BITS).find(|&i| pairs[i].0 >> bit & 1 == 1)?;
+            pairs.swap(bit, pivot);
+            for i in (0..SECRET_BITS).filter(|&i| i != bit) {
+                if pairs[i].0 >> bit & 1 == 1 {
+                    pairs[i] = (pairs[i].0 ^ pairs[bit].0, pairs[i].1 ^ pairs[bit].1);
+                }
+            }
+        }
+        Some(BitMatrix(std::array::from_fn(|bit| pairs[bit].1)))
+    }
+}
+
+// The evolution applied 2^k times, for each k a jump could need.
+static EVOLVE_POWERS: LazyLock<Vec<BitMatrix>> = LazyLock::new(|| {
+    let evolve = BitMatrix::of(|bit| {
+        let mut secret_number = SecretNumber(bit);
+        secret_number.evolve();
+        secret_number.0
+    });
+    std::iter::successors(Some(evolve), |m| Some(m.then(m))).take(64).collect()
+});
+
+static EVOLVE_INVERSE: LazyLock<BitMatrix> = LazyLock::new(
+    || EVOLVE_POWERS[0].inverse().expect("an evolution is invertible")
+);
+
+// A window of four price changes, each -9..=9, packed as a base 19 number.
+const WINDOWS: usize = 19 * 19 * 19 * 19;
+
+fn window_changes(window: usize) -> [i64; 4] {
+    [3, 2, 1, 0].map(|place| (window / 19usize.pow(place) % 19) as i64 - 9)
 }
 
 impl From<&str> for SecretNumber {
@@ -30,48 +155,75 @@ impl From<&str> for SecretNumber {
     }
 }
 
-fn keyed(v: &Vec<i64>, evolution: usize) -> String {
-    format!("{},{},{},{}", v[evolution - 3], v[evolution - 2], v[evolution - 1], v[evolution])
-}
-
 fn main() {
     let input = include_str!("input");
-    let mut secret_numbers = input.lines().map(|l| SecretNumber::from(l)).collect_vec();
-    let mut sum = 0;
-    let mut h = HashMap::new();
-    let sn_len = secret_numbers.len();
-    for (i, secret_number) in secret_numbers.iter_mut().enumerate() {
-        let mut left = secret_number.0.rem_euclid(10);
-        let mut v = vec![];
-        for evolution in 0..2000 {
-            secret_number.evolve();
-            let right = secret_number.0.rem_euclid(10);
-            v.push(right as i64 - left as i64);
-
-            if evolution >= 3 {
-                let key = keyed(&v, evolution);
-                if !h.contains_key(&key) {
-                    h.insert(key.clone(), vec![u64::MIN; sn_len]);
-                }
-                if let Some(v) = h.get_mut(&key) {
-                    if v[i] == u64::MIN {
-                        v[i] = right;
-                    }
-                }
-            }
-            left = right;
+    let secret_numbers = input.lines().map(SecretNumber::from).collect_vec();
+    let args = std::env::args().collect_vec();
+    if args.get(1).map(String::as_str) == Some("jump") {
+        let Some(n) = args.get(2).and_then(|n| n.parse::<i64>().ok()) else {
+            println!("usage: day_22 jump <n>");
+            return;
+        };
+        // a negative n steps back to the secrets these evolved from
+        let moved = |secret_number: &SecretNumber| match u64::try_from(n) {
+            Ok(n) => secret_number.jump(n),
+            Err(_) => (0..n.unsigned_abs()).fold(secret_number.clone(), |secret_number, _| secret_number.previous()),
+        };
+        for secret_number in secret_numbers.iter() {
+            println!("{}: {}", secret_number.0, moved(secret_number).0);
         }
-        sum += secret_number.0;
+        println!("Sum: {}", secret_numbers.iter().map(|secret_number| moved(secret_number).0).sum::<u64>());
+        return;
     }
+    let (sum, bananas) = SecretNumber::market(&secret_numbers, 2000);
     println!("Part 1: {}", sum);
 
-    let h: HashMap<String, u64> = h.iter().map(|(k, v)| {
-        (k.clone(), v.iter().sum::<u64>())
-    }).collect();
+    let (window, total) = bananas.iter().enumerate().max_by_key(|(_, total)| **total).unwrap();
+    println!("Part 2: {} (changes {})", total, window_changes(window).iter().join(","));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STARTS: [u64; 6] = [0, 1, 123, 2024, 16777215, 1 << 30];
 
-    let Some(m) = h.iter().max_by(|a, b| a.1.cmp(b.1)) else {
-        panic!("No max found");
-    };
+    fn evolved(start: u64, n: usize) -> SecretNumber {
+        SecretNumber(start).stream().nth(n).unwrap()
+    }
+
+    #[test]
+    fn jump_matches_repeated_evolution() {
+        for start in STARTS {
+            for n in [0, 1, 2, 3, 10, 64, 100, 1000, 2000] {
+                assert_eq!(SecretNumber(start).jump(n as u64), evolved(start, n), "{} after {}", start, n);
+            }
+        }
+    }
+
+    #[test]
+    fn large_jumps_compose() {
+        for start in STARTS {
+            for (a, b) in [(1_000_000_000, 7), (123_456_789, 987_654_321), (1 << 40, (1 << 40) + 1)] {
+                let secret_number = SecretNumber(start);
+                assert_eq!(secret_number.jump(a + b), secret_number.jump(a).jump(b), "{} with {} + {}", start, a, b);
+            }
+        }
+    }
 
-    println!("Part 2: {:?}", m.1);
+    #[test]
+    fn previous_undoes_evolution() {
+        for start in STARTS {
+            for (before, after) in SecretNumber(start).stream().skip(1).take(2000).tuple_windows() {
+                assert_eq!(after.previous(), before);
+            }
+        }
+    }
+
+    #[test]
+    fn previous_drops_bits_above_24() {
+        let mut secret_number = SecretNumber((1 << 30) | 123);
+        secret_number.evolve();
+        assert_eq!(secret_number.previous(), SecretNumber(123));
+    }
 }
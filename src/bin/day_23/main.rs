@@ -1,59 +1,134 @@
 use std::collections::{HashMap, HashSet};
 use itertools::Itertools;
+use regex::Regex;
 
 type Computer = String;
 
-type Triple = (Computer, Computer, Computer);
-
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct LocalNetwork {
     network_map: HashMap<Computer, HashSet<Computer>>,
 }
 
 impl LocalNetwork {
-    fn count_triples(&self) -> usize {
-        let mut seen: HashSet<Computer> = HashSet::new();
-        let mut triples = vec![];
-        let computers_starting_with_t = self.network_map.iter().filter(|(k, _)| k.starts_with("t"));
-        for (computer, connections) in computers_starting_with_t {
-            seen.insert(computer.clone());
-            for window in connections.iter().combinations(2) {
-                let (first, second) = (window[0], window[1]);
-                if seen.contains(first) || seen.contains(second) {
-                    continue;
-                }
-                if self.network_map.get(first).unwrap().contains(second) {
-                    triples.push((computer.clone(), first.clone(), second.clone()));
-                }
+    // Calls `f` with every clique of `k` computers exactly once. Each computer
+    // only looks forward to neighbours ranked after it by (degree, name), so
+    // a clique is only ever built up in one order, and low degree computers,
+    // which have few cliques, do most of the branching.
+    fn for_each_clique(&self, k: usize, mut f: impl FnMut(&[&Computer])) {
+        let ranked = self.network_map.keys()
+            .sorted_by_key(|&computer| (self.neighbours(computer).len(), computer))
+            .collect_vec();
+        let rank: HashMap<&Computer, usize> = ranked.iter().enumerate().map(|(i, &computer)| (computer, i)).collect();
+        let forward: HashMap<&Computer, Vec<&Computer>> = ranked.iter()
+            .map(|&computer| (computer, self.neighbours(computer).iter()
+                .filter(|neighbour| rank[neighbour] > rank[computer])
+                .sorted_by_key(|neighbour| rank[neighbour])
+                .collect()))
+            .collect();
+
+        fn extend<'a>(
+            k: usize,
+            clique: &mut Vec<&'a Computer>,
+            candidates: &[&'a Computer],
+            forward: &HashMap<&Computer, Vec<&'a Computer>>,
+            f: &mut impl FnMut(&[&Computer]),
+        ) {
+            if clique.len() == k {
+                f(clique);
+                return;
+            }
+            for (i, &computer) in candidates.iter().enumerate() {
+                let next = candidates[i + 1..].iter().copied()
+                    .filter(|candidate| forward[computer].contains(candidate))
+                    .collect_vec();
+                clique.push(computer);
+                extend(k, clique, &next, forward, f);
+                clique.pop();
             }
         }
-        return triples.len();
+
+        if k == 0 {
+            return;
+        }
+        for &computer in ranked.iter() {
+            extend(k, &mut vec![computer], &forward[computer], &forward, &mut f);
+        }
+    }
+
+    fn count_cliques(&self, k: usize, predicate: impl Fn(&Computer) -> bool) -> usize {
+        let mut count = 0;
+        self.for_each_clique(k, |clique| {
+            if clique.iter().any(|computer| predicate(computer)) {
+                count += 1;
+            }
+        });
+        count
     }
 
-    fn find_nlets(&self) -> String {
-        let mut seen: HashSet<Computer> = HashSet::new();
-        let mut max_connections = vec![];
-        for (computer, connections) in self.network_map.iter() {
-            seen.insert(computer.clone());
-            for n in (0..connections.len()).rev() {
-                if n < max_connections.len() {
-                    break;
-                }
-                'combo: for combo in connections.iter().combinations(n) {
-                    for pair in combo.iter().combinations(2) {
-                        let (&first, &second) = (pair[0], pair[1]);
-                        if !self.network_map.get(first).unwrap().contains(second) {
-                            break 'combo;
-                        }
-                    }
-                    max_connections.clear();
-                    max_connections.push(computer);
-                    max_connections.extend(combo.iter().cloned());
-                    max_connections.sort();
-                }
+    // The matching cliques, each sorted, in name order.
+    fn cliques(&self, k: usize, predicate: impl Fn(&Computer) -> bool) -> Vec<Vec<Computer>> {
+        let mut cliques = vec![];
+        self.for_each_clique(k, |clique| {
+            if clique.iter().any(|computer| predicate(computer)) {
+                cliques.push(clique.iter().map(|&computer| computer.clone()).sorted().collect_vec());
             }
+        });
+        cliques.sort();
+        cliques
+    }
+
+    fn neighbours(&self, computer: &Computer) -> &HashSet<Computer> {
+        &self.network_map[computer]
+    }
+
+    // Bron-Kerbosch with pivoting: `clique` is extended by computers from
+    // `candidates`, and `excluded` holds those whose cliques were already
+    // found. Branching only on candidates the pivot isn't connected to skips
+    // cliques that would just be found again through the pivot.
+    fn bron_kerbosch<'a>(
+        &'a self,
+        clique: &mut Vec<&'a Computer>,
+        mut candidates: HashSet<&'a Computer>,
+        mut excluded: HashSet<&'a Computer>,
+        cliques: &mut Vec<Vec<Computer>>,
+    ) {
+        if candidates.is_empty() {
+            if excluded.is_empty() {
+                cliques.push(clique.iter().map(|&computer| computer.clone()).sorted().collect());
+            }
+            return;
         }
-        max_connections.into_iter().join(",")
+        let pivot = candidates.iter().chain(excluded.iter())
+            .max_by_key(|&&pivot| self.neighbours(pivot).iter().filter(|&c| candidates.contains(c)).count())
+            .copied()
+            .unwrap();
+        let branches = candidates.iter().copied().filter(|&c| !self.neighbours(pivot).contains(c)).collect_vec();
+        for computer in branches {
+            let neighbours = self.neighbours(computer);
+            clique.push(computer);
+            self.bron_kerbosch(
+                clique,
+                // neighbour lists are short, so walk them rather than the sets
+                neighbours.iter().filter(|&c| candidates.contains(c)).collect(),
+                neighbours.iter().filter(|&c| excluded.contains(c)).collect(),
+                cliques,
+            );
+            clique.pop();
+            candidates.remove(computer);
+            excluded.insert(computer);
+        }
+    }
+
+    // Every clique that can't be grown by another computer, each sorted.
+    fn maximal_cliques(&self) -> Vec<Vec<Computer>> {
+        let mut cliques = vec![];
+        self.bron_kerbosch(&mut vec![], self.network_map.keys().collect(), HashSet::new(), &mut cliques);
+        cliques.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        cliques
+    }
+
+    fn lan_party_password(&self) -> String {
+        self.maximal_cliques().first().map(|clique| clique.join(",")).unwrap_or_default()
     }
 }
 
@@ -71,9 +146,76 @@ impl From<&str> for LocalNetwork {
     }
 }
 
+// Which computers a clique has to include one of, e.g. `prefix:t`,
+// `regex:^t[a-m]$`, `names:ka,co` or `all`.
+enum NameFilter {
+    All,
+    Prefix(String),
+    Regex(Regex),
+    Names(HashSet<Computer>),
+}
+
+impl NameFilter {
+    fn matches(&self, computer: &Computer) -> bool {
+        match self {
+            NameFilter::All => true,
+            NameFilter::Prefix(prefix) => computer.starts_with(prefix.as_str()),
+            NameFilter::Regex(re) => re.is_match(computer),
+            NameFilter::Names(names) => names.contains(computer),
+        }
+    }
+}
+
+impl TryFrom<&str> for NameFilter {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.split_once(':') {
+            _ if value == "all" => Ok(NameFilter::All),
+            Some(("prefix", prefix)) => Ok(NameFilter::Prefix(prefix.to_string())),
+            Some(("regex", re)) => Regex::new(re).map(NameFilter::Regex).map_err(|err| err.to_string()),
+            Some(("names", names)) => Ok(NameFilter::Names(names.split(',').map(str::to_string).collect())),
+            _ => Err(format!("unknown filter {:?}, expected all, prefix:<p>, regex:<re> or names:<a,b,..>", value)),
+        }
+    }
+}
+
 fn main() {
     let input = include_str!("input");
     let network = LocalNetwork::from(input);
-    println!("Part 1: {}", network.count_triples());
-    println!("Part 2: {}", network.find_nlets());
+    let args = std::env::args().collect_vec();
+    if args.get(1).map(String::as_str) == Some("cliques") {
+        // maximal cliques grouped by size, largest first, or just one size
+        let size: Option<usize> = match args.get(2).map(|size| size.parse()) {
+            None => None,
+            Some(Ok(size)) => Some(size),
+            Some(Err(_)) => return println!("usage: day_23 cliques [size]"),
+        };
+        for (len, cliques) in &network.maximal_cliques().into_iter().chunk_by(|clique| clique.len()) {
+            let cliques = cliques.collect_vec();
+            match size {
+                Some(size) if size == len => cliques.iter().for_each(|clique| println!("{}", clique.join(","))),
+                Some(_) => {}
+                None => println!("{}: {}", len, cliques.len()),
+            }
+        }
+        return;
+    }
+    if let Some(command @ ("count" | "list")) = args.get(1).map(String::as_str) {
+        let Some(k) = args.get(2).and_then(|k| k.parse().ok()) else {
+            return println!("usage: day_23 count|list <k> [filter]");
+        };
+        let filter = match NameFilter::try_from(args.get(3).map_or("all", String::as_str)) {
+            Ok(filter) => filter,
+            Err(err) => return println!("{}", err),
+        };
+        if command == "count" {
+            println!("{}", network.count_cliques(k, |computer| filter.matches(computer)));
+        } else {
+            network.cliques(k, |computer| filter.matches(computer)).iter().for_each(|clique| println!("{}", clique.join(",")));
+        }
+        return;
+    }
+    println!("Part 1: {}", network.count_cliques(3, |computer| computer.starts_with('t')));
+    println!("Part 2: {}", network.lan_party_password());
 }
\ No newline at end of file
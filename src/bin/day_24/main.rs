@@ -1,8 +1,12 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::fmt::{Display, Formatter};
 use std::ops::BitXor;
 use std::sync::LazyLock;
 use itertools::Itertools;
 use regex::Regex;
+use advent_of_code_2024::lint;
+use advent_of_code_2024::repl::{arg, Repl};
 
 static RE_WIRE: LazyLock<Regex> = LazyLock::new(
     || Regex::new(r"(.{3}): (\d)").unwrap()
@@ -14,6 +18,8 @@ static RE_BINOP: LazyLock<Regex> = LazyLock::new(
 
 
 type Wires = HashMap<String, u64>;
+// z is a bit wider than x and y, so a 64-bit adder's sum doesn't fit a u64
+type Sum = u128;
 
 #[derive(Clone, Debug, Eq, PartialEq, Hash)]
 enum BinOp {
@@ -40,6 +46,23 @@ struct Op {
     c: String,
 }
 
+impl Op {
+    fn reads(&self, wire: &str) -> bool {
+        self.a == wire || self.b == wire
+    }
+
+    fn other_input(&self, wire: &str) -> &String {
+        if self.a == wire { &self.b } else { &self.a }
+    }
+}
+
+// What checking one bit of the adder found: either the bit is wired like a
+// full adder and produces this carry, or these two outputs need swapping.
+enum AdderBit {
+    CarryOut(String),
+    Swap(String, String),
+}
+
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 struct Device {
@@ -76,49 +99,15 @@ impl Device {
     }
 
 
-    fn eval(&mut self) -> u64 {
-        let mut z_wires = self.ops_map
-            .keys()
-            .filter(|&k| k.starts_with("z"))
-            .map(|op| op.clone())
-            .collect_vec();
-        z_wires.sort_by(|a, b| a.cmp(b));
-
-        for result_wire in z_wires.clone() {
-            self.eval_op(&self.ops_map.get(&result_wire).unwrap().clone(), &mut HashSet::new());
-        }
-        self.wires_str_to_u64("z".to_string())
+    fn compile(&self) -> Result<Circuit, CircuitError> {
+        Circuit::compile(self)
     }
 
-    fn eval_op(&mut self, op: &Op, seen: &mut HashSet<Op>) -> Option<u64> {
-        // more complex than needed from testing part 2 stuff out, need to clean up
-        if seen.contains(op) {
-            return None;
-        }
-        seen.insert(op.clone());
-        let a = if let Some(&a) = self.wires.get(&op.a) {
-            a
-        } else {
-            let op_for_a = self.ops_map.get(&op.a).unwrap().clone();
-            let a = self.eval_op(&op_for_a, seen);
-            if a.is_none() {
-                return None;
-            }
-            a.unwrap()
-        };
-        let b = if let Some(&b) = self.wires.get(&op.b) {
-            b
-        } else {
-            let op_for_b = self.ops_map.get(&op.b).unwrap().clone();
-            let b = self.eval_op(&op_for_b, seen);
-            if b.is_none() {
-                return None;
-            }
-            b.unwrap()
-        };
-        let c = op.op.eval(a, b);
-        self.wires.insert(op.c.clone(), c);
-        Some(c)
+    // Evaluates z for the input wires given in the puzzle.
+    fn eval(&self) -> Result<Sum, CircuitError> {
+        let circuit = self.compile()?;
+        let (x, y) = (self.wires_str_to_u64("x".to_string()), self.wires_str_to_u64("y".to_string()));
+        Ok(circuit.eval(x, y, &mut vec![]))
     }
 
     // fn find_dependencies(&self, wire: &str) -> HashSet<Op> {
@@ -137,15 +126,571 @@ impl Device {
     //     deps
     // }
 
-    fn swap(&mut self, a: String, b: String) {
-        let a_op = self.ops_map.get(&a).unwrap().clone();
-        let b_op = self.ops_map.get(&b).unwrap().clone();
+    fn width(&self) -> usize {
+        self.wires.keys().filter(|wire| wire.starts_with('x')).count()
+    }
+
+    fn find_gate(&self, op: &BinOp, a: &str, b: &str) -> Option<&Op> {
+        self.ops_map.values().find(|gate| gate.op == *op && gate.reads(a) && gate.reads(b))
+    }
+
+    fn find_gate_reading(&self, op: &BinOp, a: &str) -> Option<&Op> {
+        self.ops_map.values().find(|gate| gate.op == *op && gate.reads(a))
+    }
+
+    // Bit `i` of a ripple-carry adder is a full adder:
+    //   half_sum = xi XOR yi,  half_carry = xi AND yi
+    //   zi = half_sum XOR carry_in
+    //   carry_out = half_carry OR (half_sum AND carry_in)
+    // and bit 0 is just the half adder. Gate inputs are trusted, since only
+    // outputs get swapped, so any gate found by its inputs is the right gate
+    // and a wrong name on its output is a swap.
+    fn check_adder_bit(&self, i: usize, carry_in: Option<&str>) -> Result<AdderBit, String> {
+        let (x, y, z) = (format!("x{:02}", i), format!("y{:02}", i), format!("z{:02}", i));
+        let half_sum = &self.find_gate(&BinOp::Xor, &x, &y).ok_or(format!("bit {}: no {} XOR {}", i, x, y))?.c;
+        let half_carry = &self.find_gate(&BinOp::And, &x, &y).ok_or(format!("bit {}: no {} AND {}", i, x, y))?.c;
+        let Some(carry_in) = carry_in else {
+            if *half_sum != z {
+                return self.propose_swap(i, half_sum.clone(), z);
+            }
+            return Ok(AdderBit::CarryOut(half_carry.clone()));
+        };
+
+        let Some(sum) = self.find_gate(&BinOp::Xor, half_sum, carry_in) else {
+            // whichever input the sum gate does read shows what the other should be
+            if let Some(sum) = self.find_gate_reading(&BinOp::Xor, carry_in) {
+                return self.propose_swap(i, half_sum.clone(), sum.other_input(carry_in).clone());
+            }
+            if let Some(sum) = self.find_gate_reading(&BinOp::Xor, half_sum) {
+                return self.propose_swap(i, carry_in.to_string(), sum.other_input(half_sum).clone());
+            }
+            return Err(format!("bit {}: no XOR reads {} or {}", i, half_sum, carry_in));
+        };
+        if sum.c != z {
+            return self.propose_swap(i, sum.c.clone(), z);
+        }
+
+        let propagated = &self.find_gate(&BinOp::And, half_sum, carry_in)
+            .ok_or(format!("bit {}: no {} AND {}", i, half_sum, carry_in))?.c;
+        let Some(carry_out) = self.find_gate(&BinOp::Or, half_carry, propagated) else {
+            if let Some(carry_out) = self.find_gate_reading(&BinOp::Or, half_carry) {
+                return self.propose_swap(i, propagated.clone(), carry_out.other_input(half_carry).clone());
+            }
+            if let Some(carry_out) = self.find_gate_reading(&BinOp::Or, propagated) {
+                return self.propose_swap(i, half_carry.clone(), carry_out.other_input(propagated).clone());
+            }
+            return Err(format!("bit {}: no OR reads {} or {}", i, half_carry, propagated));
+        };
+        Ok(AdderBit::CarryOut(carry_out.c.clone()))
+    }
+
+    // Only gate outputs can be swapped, so a fix that would need to move an
+    // input wire, or a z bit nothing drives, means the circuit isn't an adder
+    // with swapped outputs at all.
+    fn propose_swap(&self, i: usize, a: String, b: String) -> Result<AdderBit, String> {
+        if let Some(wire) = [&a, &b].into_iter().find(|wire| !self.ops_map.contains_key(*wire)) {
+            return Err(format!("bit {}: would swap {} and {}, but no gate drives {}", i, a, b, wire));
+        }
+        Ok(AdderBit::Swap(a, b))
+    }
+
+    // Walks the adder from the lowest bit up, swapping outputs wherever a bit
+    // doesn't match the full adder pattern, then checks the repaired circuit
+    // really adds. Returns the swapped wires, sorted.
+    fn repair_adder(&mut self) -> Result<Vec<String>, String> {
+        let width = self.width();
+        let mut swapped = vec![];
+        let mut carry: Option<String> = None;
+        for i in 0..width {
+            // a bit has five gates, so needing more swaps than that means the
+            // analysis is going in circles
+            let mut carry_out = None;
+            for _ in 0..5 {
+                match self.check_adder_bit(i, carry.as_deref())? {
+                    AdderBit::CarryOut(c) => {
+                        carry_out = Some(c);
+                        break;
+                    }
+                    AdderBit::Swap(a, b) => {
+                        self.swap(a.clone(), b.clone())?;
+                        swapped.extend([a, b]);
+                    }
+                }
+            }
+            carry = Some(carry_out.ok_or(format!("bit {}: still miswired after 5 swaps", i))?);
+        }
+        let last_z = format!("z{:02}", width);
+        if let Some(carry) = carry.filter(|carry| *carry != last_z) {
+            self.swap(carry.clone(), last_z.clone())?;
+            swapped.extend([carry, last_z]);
+        }
+        self.verify_adder()?;
+        swapped.sort();
+        Ok(swapped)
+    }
+
+    fn verify_adder(&self) -> Result<(), String> {
+        let report = self.compile().map_err(|err| err.to_string())?.diff(Expected::Add);
+        match report.mismatches.first() {
+            Some(m) => Err(format!("{} + {} gave {}, expected {}", m.x, m.y, m.z, m.expected)),
+            None => Ok(()),
+        }
+    }
+
+    // Overwrites the initial `x` or `y` wires with the bits of `value`.
+    fn set_input(&mut self, prefix: char, value: u64) {
+        for (wire, bit) in self.wires.iter_mut() {
+            if let Some(i) = wire.strip_prefix(prefix).and_then(|i| i.parse::<u32>().ok()) {
+                *bit = value.checked_shr(i).unwrap_or(0) & 1;
+            }
+        }
+    }
+
+    fn swap(&mut self, a: String, b: String) -> Result<(), String> {
+        let driver = |wire: &String| self.ops_map.get(wire).cloned().ok_or(format!("no gate drives wire: {}", wire));
+        let (a_op, b_op) = (driver(&a)?, driver(&b)?);
         let mut a_op_next = a_op.clone();
         let mut b_op_next = b_op.clone();
         a_op_next.c = b_op.c.clone();
         b_op_next.c = a_op.c.clone();
         self.ops_map.insert(a_op_next.c.clone(), a_op_next);
         self.ops_map.insert(b_op_next.c.clone(), b_op_next);
+        Ok(())
+    }
+}
+
+// What a circuit is supposed to compute from its x and y inputs.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Expected {
+    Add,
+    And,
+    Or,
+}
+
+impl Expected {
+    fn apply(&self, x: u64, y: u64) -> Sum {
+        match self {
+            Expected::Add => x as Sum + y as Sum,
+            Expected::And => (x & y) as Sum,
+            Expected::Or => (x | y) as Sum,
+        }
+    }
+}
+
+impl std::str::FromStr for Expected {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "add" | "+" => Ok(Expected::Add),
+            "and" | "&" => Ok(Expected::And),
+            "or" | "|" => Ok(Expected::Or),
+            _ => Err(format!("unknown function: {}", s)),
+        }
+    }
+}
+
+struct Mismatch {
+    x: u64,
+    y: u64,
+    z: Sum,
+    expected: Sum,
+}
+
+// How a circuit's z compares to the expected function over a set of inputs.
+// Small circuits are checked exhaustively and get a full truth table.
+struct DiffReport {
+    expected: Expected,
+    x_width: usize,
+    z_width: usize,
+    n_cases: usize,
+    mismatches: Vec<Mismatch>,
+    truth_table: Option<Vec<(u64, u64, Sum)>>,
+}
+
+// Widths up to this are checked on every input pair (4^4 = 256 rows).
+const EXHAUSTIVE_WIDTH: usize = 4;
+
+fn mask(width: usize) -> u64 {
+    if width == 0 { 0 } else { u64::MAX >> (64 - width.min(64)) }
+}
+
+fn sum_mask(width: usize) -> Sum {
+    if width == 0 { 0 } else { Sum::MAX >> (Sum::BITS as usize - width.min(Sum::BITS as usize)) }
+}
+
+// A spread of inputs for a `width`-bit circuit: all zeros, all ones, single
+// carries rippling through every bit, and deterministic pseudo-random pairs.
+fn sample_inputs(width: usize) -> Vec<(u64, u64)> {
+    let mask = mask(width);
+    if width <= EXHAUSTIVE_WIDTH {
+        return (0..=mask).cartesian_product(0..=mask).collect();
+    }
+    let mut rng = 0x2024_1224_u64;
+    let mut next = || {
+        rng ^= rng << 13;
+        rng ^= rng >> 7;
+        rng ^= rng << 17;
+        rng & mask
+    };
+    let mut cases = vec![(0, 0), (mask, mask), (mask, 1)];
+    cases.extend((0..width).map(|i| (1 << i, 1 << i)));
+    cases.extend((0..100).map(|_| (next(), next())));
+    cases
+}
+
+impl Display for DiffReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let op = match self.expected {
+            Expected::Add => "+",
+            Expected::And => "&",
+            Expected::Or => "|",
+        };
+        writeln!(f, "{} of {} inputs give the wrong z for x {} y", self.mismatches.len(), self.n_cases, op)?;
+        let z_width = self.z_width;
+        let wrong_per_bit = (0..z_width)
+            .map(|bit| self.mismatches.iter().filter(|m| (m.z ^ m.expected) >> bit & 1 == 1).count())
+            .collect_vec();
+        for (bit, n) in wrong_per_bit.iter().enumerate().filter(|(_, n)| **n > 0) {
+            writeln!(f, "  z{:02} wrong for {} inputs", bit, n)?;
+        }
+        for m in self.mismatches.iter().take(5) {
+            writeln!(f, "x = {}, y = {}", m.x, m.y)?;
+            writeln!(f, "  z        {:0w$b}", m.z, w = z_width)?;
+            writeln!(f, "  expected {:0w$b}", m.expected, w = z_width)?;
+            let diff: String = (0..z_width).rev()
+                .map(|bit| if (m.z ^ m.expected) >> bit & 1 == 1 { '^' } else { ' ' })
+                .collect();
+            writeln!(f, "           {}", diff.trim_end())?;
+        }
+        if self.mismatches.len() > 5 {
+            writeln!(f, "... and {} more", self.mismatches.len() - 5)?;
+        }
+        if let Some(rows) = &self.truth_table {
+            let (xw, zw) = (self.x_width.max(1), z_width);
+            writeln!(f)?;
+            writeln!(f, "{:>xw$} {:>xw$} | {} | expected", "x", "y",
+                (0..zw).rev().map(|bit| format!("z{:02}", bit)).join(" "))?;
+            for &(x, y, z) in rows {
+                let expected = self.expected.apply(x, y);
+                let bits = (0..zw).rev()
+                    .map(|bit| {
+                        let wrong = if (z ^ expected) >> bit & 1 == 1 { '!' } else { ' ' };
+                        format!("{:>2}{} ", z >> bit & 1, wrong)
+                    })
+                    .join("");
+                writeln!(f, "{:0xw$b} {:0xw$b} | {}| {:0zw$b}", x, y, bits, expected)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+type WireId = usize;
+
+#[derive(Clone, Debug)]
+struct Gate {
+    op: BinOp,
+    a: WireId,
+    b: WireId,
+    c: WireId,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum CircuitError {
+    // wires in dependency order, the last one feeding back into the first
+    Cycle(Vec<String>),
+    Undriven(String),
+}
+
+impl Display for CircuitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitError::Cycle(wires) => write!(f, "cycle {} -> {}", wires.join(" -> "), wires[0]),
+            CircuitError::Undriven(wire) => write!(f, "{} is read but never set", wire),
+        }
+    }
+}
+
+// A `Device` compiled for repeated evaluation: wire names are interned to
+// ids and the gates sorted topologically once, so evaluating is one linear
+// pass over `gates` writing into a flat buffer of wire values.
+#[derive(Clone, Debug)]
+struct Circuit {
+    names: Vec<String>,
+    gates: Vec<Gate>,
+    x: Vec<WireId>,
+    y: Vec<WireId>,
+    z: Vec<WireId>,
+}
+
+impl Circuit {
+    fn compile(device: &Device) -> Result<Circuit, CircuitError> {
+        let mut ids: HashMap<String, WireId> = HashMap::new();
+        let mut names: Vec<String> = vec![];
+        let mut intern = |name: &String| *ids.entry(name.clone()).or_insert_with(|| {
+            names.push(name.clone());
+            names.len() - 1
+        });
+        // interning in name order keeps ids, and so everything derived from
+        // them, the same from run to run
+        for wire in device.wires.keys().sorted() {
+            intern(wire);
+        }
+        let gates = device.ops_map.keys().sorted()
+            .map(|wire| {
+                let op = &device.ops_map[wire];
+                Gate { op: op.op.clone(), a: intern(&op.a), b: intern(&op.b), c: intern(&op.c) }
+            })
+            .collect_vec();
+
+        let mut driver: Vec<Option<usize>> = vec![None; names.len()];
+        for (i, gate) in gates.iter().enumerate() {
+            driver[gate.c] = Some(i);
+        }
+        if let Some(wire) = (0..names.len()).find(|&id| driver[id].is_none() && !device.wires.contains_key(&names[id])) {
+            return Err(CircuitError::Undriven(names[wire].clone()));
+        }
+
+        // Kahn's algorithm: a gate is ready once every gate driving its inputs is
+        let mut readers: Vec<Vec<usize>> = vec![vec![]; names.len()];
+        let mut pending = vec![0; gates.len()];
+        for (i, gate) in gates.iter().enumerate() {
+            for input in [gate.a, gate.b] {
+                if driver[input].is_some() {
+                    readers[input].push(i);
+                    pending[i] += 1;
+                }
+            }
+        }
+        let mut ready = (0..gates.len()).filter(|&i| pending[i] == 0).collect::<VecDeque<_>>();
+        let mut order = vec![];
+        while let Some(i) = ready.pop_front() {
+            order.push(i);
+            for &reader in readers[gates[i].c].iter() {
+                pending[reader] -= 1;
+                if pending[reader] == 0 {
+                    ready.push_back(reader);
+                }
+            }
+        }
+        if order.len() < gates.len() {
+            return Err(CircuitError::Cycle(Self::find_cycle(&gates, &driver, &pending, &names)));
+        }
+
+        let bits = |prefix: &str| {
+            let mut bits = (0..names.len()).filter(|&id| names[id].starts_with(prefix)).collect_vec();
+            bits.sort_by(|a, b| names[*a].cmp(&names[*b]));
+            bits
+        };
+        let (x, y) = (bits("x"), bits("y"));
+        let z = bits("z").into_iter().filter(|id| driver[*id].is_some()).collect();
+        let gates = order.into_iter().map(|i| gates[i].clone()).collect();
+        Ok(Circuit { names, gates, x, y, z })
+    }
+
+    // Every gate Kahn's algorithm couldn't schedule still waits on another
+    // unscheduled gate, so walking those dependencies must come back around.
+    fn find_cycle(gates: &[Gate], driver: &[Option<usize>], pending: &[usize], names: &[String]) -> Vec<String> {
+        let waiting = |id: WireId| driver[id].filter(|&i| pending[i] > 0);
+        let mut gate = (0..gates.len()).find(|&i| pending[i] > 0).unwrap();
+        let mut path: Vec<usize> = vec![];
+        while !path.contains(&gate) {
+            path.push(gate);
+            gate = waiting(gates[gate].a).or(waiting(gates[gate].b)).unwrap();
+        }
+        let start = path.iter().position(|&i| i == gate).unwrap();
+        // path follows inputs backwards, reverse it to read in dependency order
+        path[start..].iter().rev().map(|&i| names[gates[i].c].clone()).collect()
+    }
+
+    fn diff(&self, expected: Expected) -> DiffReport {
+        let cases = sample_inputs(self.x.len());
+        let mut values = vec![];
+        let results = cases.iter().map(|&(x, y)| (x, y, self.eval(x, y, &mut values))).collect_vec();
+        let z_mask = sum_mask(self.z.len());
+        DiffReport {
+            expected,
+            x_width: self.x.len(),
+            z_width: self.z.len(),
+            n_cases: cases.len(),
+            mismatches: results.iter()
+                .filter(|&&(x, y, z)| z != expected.apply(x, y) & z_mask)
+                .map(|&(x, y, z)| Mismatch { x, y, z, expected: expected.apply(x, y) & z_mask })
+                .collect(),
+            truth_table: (self.x.len() <= EXHAUSTIVE_WIDTH).then_some(results),
+        }
+    }
+
+    // `values` is scratch space, passed in so repeated evaluations can reuse it.
+    fn eval(&self, x: u64, y: u64, values: &mut Vec<u64>) -> Sum {
+        values.clear();
+        values.resize(self.names.len(), 0);
+        for (i, &id) in self.x.iter().enumerate() {
+            values[id] = (x >> i) & 1;
+        }
+        for (i, &id) in self.y.iter().enumerate() {
+            values[id] = (y >> i) & 1;
+        }
+        for gate in self.gates.iter() {
+            values[gate.c] = gate.op.eval(values[gate.a], values[gate.b]);
+        }
+        self.z.iter().enumerate().fold(0, |z, (i, &id)| z | (values[id] as Sum) << i)
+    }
+}
+
+// Exporters and an importer so circuits can be inspected in Graphviz and
+// exchanged with tools that speak (a small subset of) structural Verilog.
+impl Device {
+    fn bits(&self, prefix: &str) -> Vec<String> {
+        self.wires.keys().chain(self.ops_map.keys())
+            .filter(|wire| wire.starts_with(prefix))
+            .unique()
+            .sorted()
+            .cloned()
+            .collect()
+    }
+
+    // Gates are nodes named after the wire they drive and coloured by
+    // operation; inputs and outputs are pinned to the edges of the graph in
+    // bit order, and `suspects` are drawn in red.
+    fn to_dot(&self, suspects: &[String]) -> String {
+        let mut dot = String::from("digraph device {\n    rankdir=LR;\n    node [style=filled, fontname=monospace];\n");
+        for (prefix, rank) in [("x", "source"), ("y", "source"), ("z", "sink")] {
+            let bits = self.bits(prefix);
+            dot.push_str(&format!("    subgraph {{ rank={}; {}; }}\n", rank, bits.join("; ")));
+            // invisible edges keep the bits in order within the rank
+            if bits.len() > 1 {
+                dot.push_str(&format!("    {} [style=invis];\n", bits.join(" -> ")));
+            }
+        }
+        for wire in self.bits("x").iter().chain(self.bits("y").iter()) {
+            dot.push_str(&format!("    {} [shape=box, fillcolor=lightgrey];\n", wire));
+        }
+        for wire in self.ops_map.keys().sorted() {
+            let op = &self.ops_map[wire];
+            let (name, color) = match op.op {
+                BinOp::And => ("AND", "lightblue"),
+                BinOp::Or => ("OR", "palegreen"),
+                BinOp::Xor => ("XOR", "orange"),
+            };
+            let shape = if wire.starts_with('z') { "doublecircle" } else { "ellipse" };
+            let highlight = if suspects.contains(wire) { ", color=red, penwidth=3" } else { "" };
+            dot.push_str(&format!("    {} [label=\"{}\\n{}\", shape={}, fillcolor={}{}];\n", wire, name, wire, shape, color, highlight));
+            dot.push_str(&format!("    {} -> {};\n    {} -> {};\n", op.a, wire, op.b, wire));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn netlist_signal(wire: &str) -> String {
+        match wire.split_at(1) {
+            (bus @ ("x" | "y" | "z"), bit) if bit.parse::<usize>().is_ok() => format!("{}[{}]", bus, bit.parse::<usize>().unwrap()),
+            _ => wire.to_string(),
+        }
+    }
+
+    fn to_netlist(&self) -> String {
+        let (x, y, z) = (self.bits("x").len(), self.bits("y").len(), self.bits("z").len());
+        let mut netlist = format!(
+            "module device(input [{}:0] x, input [{}:0] y, output [{}:0] z);\n",
+            x.saturating_sub(1), y.saturating_sub(1), z.saturating_sub(1),
+        );
+        let internal = self.ops_map.keys().filter(|wire| !wire.starts_with('z')).sorted().join(", ");
+        if !internal.is_empty() {
+            netlist.push_str(&format!("    wire {};\n", internal));
+        }
+        for wire in self.ops_map.keys().sorted() {
+            let op = &self.ops_map[wire];
+            let gate = match op.op {
+                BinOp::And => "and",
+                BinOp::Or => "or",
+                BinOp::Xor => "xor",
+            };
+            netlist.push_str(&format!(
+                "    {} g_{}({}, {}, {});\n",
+                gate, wire, Self::netlist_signal(wire), Self::netlist_signal(&op.a), Self::netlist_signal(&op.b),
+            ));
+        }
+        netlist.push_str("endmodule\n");
+        netlist
+    }
+
+    // Reads back what `to_netlist` writes. Netlists carry no input values, so
+    // every x and y wire starts at 0.
+    fn from_netlist(netlist: &str) -> Result<Device, String> {
+        let signal = |raw: &str| -> Result<String, String> {
+            let raw = raw.trim();
+            match raw.split_once('[') {
+                Some((bus, bit)) => {
+                    let bit: usize = bit.strip_suffix(']').and_then(|bit| bit.parse().ok())
+                        .ok_or(format!("invalid bit select: {}", raw))?;
+                    Ok(format!("{}{:02}", bus, bit))
+                }
+                None if !raw.is_empty() && raw.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => Ok(raw.to_string()),
+                None => Err(format!("invalid signal: {}", raw)),
+            }
+        };
+        let width = |header: &str, name: &str| -> Result<usize, String> {
+            let decl = header.split([',', '(']).find(|decl| decl.trim().trim_end_matches([')', ';']).ends_with(&format!("] {}", name)))
+                .ok_or(format!("no port {} in module header", name))?;
+            let msb = decl.split_once('[').and_then(|(_, range)| range.split_once(':'))
+                .and_then(|(msb, _)| msb.trim().parse::<usize>().ok())
+                .ok_or(format!("invalid width for port {}", name))?;
+            Ok(msb + 1)
+        };
+
+        let mut device = Device { wires: Wires::new(), ops_map: HashMap::new() };
+        let mut in_module = false;
+        for (i, line) in netlist.lines().enumerate() {
+            let at = |err: String| format!("line {}: {}", i + 1, err);
+            let line = line.split("//").next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with("module") {
+                for bus in ["x", "y"] {
+                    for bit in 0..width(line, bus).map_err(at)? {
+                        device.wires.insert(format!("{}{:02}", bus, bit), 0);
+                    }
+                }
+                width(line, "z").map_err(at)?;
+                in_module = true;
+                continue;
+            }
+            if !in_module {
+                return Err(at(format!("expected a module header, found: {}", line)));
+            }
+            if line == "endmodule" {
+                in_module = false;
+                continue;
+            }
+            if line.starts_with("wire ") {
+                continue;
+            }
+            let (gate, rest) = line.split_once(char::is_whitespace).ok_or(at(format!("unrecognised statement: {}", line)))?;
+            let op = match gate {
+                "and" => BinOp::And,
+                "or" => BinOp::Or,
+                "xor" => BinOp::Xor,
+                _ => return Err(at(format!("unknown gate type: {}", gate))),
+            };
+            let ports = rest.split_once('(')
+                .and_then(|(_, ports)| ports.trim_end().strip_suffix(");"))
+                .ok_or(at(format!("expected '{} <name>(<out>, <a>, <b>);'", gate)))?;
+            let ports: Vec<String> = ports.split(',').map(signal).collect::<Result<_, _>>().map_err(at)?;
+            let [c, a, b] = ports.as_slice() else {
+                return Err(at(format!("{} gate needs 3 ports, found {}", gate, ports.len())));
+            };
+            if device.ops_map.contains_key(c) {
+                return Err(at(format!("{} is driven twice", c)));
+            }
+            device.ops_map.insert(c.clone(), Op { op, a: a.clone(), b: b.clone(), c: c.clone() });
+        }
+        if in_module {
+            return Err("missing endmodule".to_string());
+        }
+        Ok(device)
     }
 }
 
@@ -199,29 +744,205 @@ impl From<&str> for Device {
     }
 }
 
+fn repl(device: Device) {
+    Repl::new("day 24", device)
+        .command("eval", "", "evaluate the circuit and print z", |device, _| {
+            Ok(device.eval().map_err(|err| err.to_string())?.to_string())
+        })
+        .command("xyz", "", "print x, y, z and whether z == x + y", |device, _| {
+            let z = device.eval().map_err(|err| err.to_string())?;
+            let x = device.wires_str_to_u64("x".to_string());
+            let y = device.wires_str_to_u64("y".to_string());
+            Ok(format!("x = {}\ny = {}\nz = {}\nz == x + y: {}", x, y, z, z == x as Sum + y as Sum))
+        })
+        .command("wire", "<name>", "show the input value or gate driving a wire", |device, args| {
+            let name: String = arg(args, 0, "name")?;
+            if let Some(value) = device.wires.get(&name) {
+                Ok(format!("{}: {}", name, value))
+            } else if let Some(op) = device.ops_map.get(&name) {
+                Ok(format!("{} {:?} {} -> {}", op.a, op.op, op.b, op.c))
+            } else {
+                Err(format!("no such wire: {}", name))
+            }
+        })
+        .command("swap", "<a> <b>", "swap the outputs of the gates driving two wires", |device, args| {
+            let a: String = arg(args, 0, "a")?;
+            let b: String = arg(args, 1, "b")?;
+            device.swap(a.clone(), b.clone())?;
+            Ok(format!("swapped {} <-> {}", a, b))
+        })
+        .command("set", "<x|y> <n>", "set the x or y input wires to the bits of n", |device, args| {
+            let prefix: char = arg(args, 0, "x|y")?;
+            if prefix != 'x' && prefix != 'y' {
+                return Err(format!("can only set x or y, not {}", prefix));
+            }
+            device.set_input(prefix, arg(args, 1, "n")?);
+            Ok(format!("{} = {}", prefix, device.wires_str_to_u64(prefix.to_string())))
+        })
+        .command("diff", "[add|and|or]", "compare z against x + y (or x & y, x | y) over many inputs", |device, args| {
+            let expected = if args.is_empty() { Expected::Add } else { arg(args, 0, "function")? };
+            Ok(device.compile().map_err(|err| err.to_string())?.diff(expected).to_string())
+        })
+        .command("repair", "", "find and swap miswired outputs until the circuit adds", |device, _| {
+            Ok(device.repair_adder()?.join(","))
+        })
+        .run();
+}
+
+fn lint(input: &str) -> lint::Report {
+    let mut report = lint::Report::new();
+    let Some((wires_str, ops_str)) = input.split("\n\n").collect_tuple() else {
+        report.check("input is initial wires and gates separated by a blank line", vec![
+            "expected exactly two sections".to_string()
+        ]);
+        return report;
+    };
+    report
+        .check("every initial wire is '<name>: <0|1>'", wires_str.lines()
+            .filter(|line| !RE_WIRE.is_match(line) || !(line.ends_with(": 0") || line.ends_with(": 1")))
+            .map(|line| format!("malformed wire: {}", line))
+            .collect())
+        .check("every gate is '<a> AND|OR|XOR <b> -> <c>'", ops_str.lines()
+            .filter(|line| RE_BINOP.captures(line).is_none_or(|c| !["AND", "OR", "XOR"].contains(&&c[2])))
+            .map(|line| format!("malformed gate: {}", line))
+            .collect());
+    if !report.is_ok() {
+        return report;
+    }
+
+    let outputs = ops_str.lines().map(|line| RE_BINOP.captures(line).unwrap()[4].to_string()).counts();
+    let device = Device::from(input);
+    let mut duplicates = outputs.iter().filter(|(_, n)| **n > 1)
+        .map(|(wire, n)| format!("{} is driven by {} gates", wire, n))
+        .collect_vec();
+    duplicates.sort();
+    let mut undriven = device.ops_map.values()
+        .flat_map(|op| [&op.a, &op.b])
+        .filter(|wire| !device.wires.contains_key(*wire) && !device.ops_map.contains_key(*wire))
+        .unique()
+        .map(|wire| format!("{} is read but never set", wire))
+        .collect_vec();
+    undriven.sort();
+    report
+        .check("every wire is driven by at most one gate", duplicates)
+        .check("every gate input is an initial wire or a gate output", undriven)
+        .check("the circuit has no cycles", match device.compile() {
+            Err(err @ CircuitError::Cycle(_)) => vec![err.to_string()],
+            _ => vec![],
+        });
+
+    let bits = |prefix: &str| {
+        let mut names = device.wires.keys().chain(device.ops_map.keys())
+            .filter(|wire| wire.starts_with(prefix))
+            .cloned()
+            .collect_vec();
+        names.sort();
+        names
+    };
+    let (x, y, z) = (bits("x"), bits("y"), bits("z"));
+    let contiguous = |prefix: &str, names: &Vec<String>| (0..names.len())
+        .map(|i| format!("{}{:02}", prefix, i))
+        .filter(|name| !names.contains(name))
+        .map(|name| format!("{} is missing", name))
+        .collect_vec();
+    report
+        .check("x, y and z bits are numbered from 00 without gaps", [("x", &x), ("y", &y), ("z", &z)].iter()
+            .flat_map(|(prefix, names)| contiguous(prefix, names))
+            .collect())
+        .check("x and y have the same width and z is one bit wider", if x.len() == y.len() && z.len() == x.len() + 1 {
+            vec![]
+        } else {
+            vec![format!("x has {} bits, y has {}, z has {}", x.len(), y.len(), z.len())]
+        });
+
+    // The swaps Part 2 looks for only ever move gate outputs around, so what
+    // `repair_adder` relies on holds even in a miswired input: each bit reads
+    // its inputs into one XOR and one AND, and the whole adder is five gates
+    // a bit, less three for the half adder at bit 0.
+    let input_gates = |i: usize, op: BinOp| {
+        let inputs = [format!("x{:02}", i), format!("y{:02}", i)];
+        device.ops_map.values()
+            .filter(|gate| gate.op == op && inputs.contains(&gate.a) && inputs.contains(&gate.b) && gate.a != gate.b)
+            .count()
+    };
+    let gates = ops_str.lines().count();
+    let expected_gates = (5 * x.len()).saturating_sub(3);
+    report
+        .check("every x and y bit pair feeds exactly one XOR and one AND", (0..x.len())
+            .flat_map(|i| [BinOp::Xor, BinOp::And].map(|op| (i, op)))
+            .filter_map(|(i, op)| match input_gates(i, op.clone()) {
+                1 => None,
+                n => Some(format!("x{:02} and y{:02} feed {} {:?} gates", i, i, n, op)),
+            })
+            .collect())
+        .check("the adder has five gates per bit, less three for bit 00", if gates == expected_gates {
+            vec![]
+        } else {
+            vec![format!("{} gates for {} bits, expected {}", gates, x.len(), expected_gates)]
+        });
+    report
+}
+
 fn main() {
     let input = include_str!("input");
+    if std::env::args().nth(1).as_deref() == Some("lint") {
+        lint(input).finish();
+    }
     let mut base_device: Device = input.into();
-    let mut device = base_device.clone();
-    let n = device.eval();
-    println!("Part 1: {:?}", n);
-
-    // was able to figure out part 2 by hand, need to do by code
-    // let x_wires_n = device.wires_str_to_u64("x".to_string());
-    // let y_wires_n = device.wires_str_to_u64("y".to_string());
-    // println!("{:#064b}", x_wires_n);
-    // println!("{:#064b}", y_wires_n);
-    // let sum = x_wires_n + y_wires_n;
-    // device.swap("z11".to_string(), "wpd".to_string());
-    // device.swap("skh".to_string(), "jqf".to_string());
-    // device.swap("z19".to_string(), "cmp".to_string());
-    // device.swap("rhh".to_string(), "wts".to_string());
-    // let mut v = vec![
-    //     "z11".to_string(), "wpd".to_string(),
-    //     "skh".to_string(), "jqf".to_string(),
-    //     "z19".to_string(), "cmp".to_string(),
-    //     "rhh".to_string(), "wts".to_string(),
-    // ];
-    // v.sort();
-    // println!("{}", v.join(","));
+    let args = std::env::args().collect_vec();
+    match args.get(1).map(String::as_str) {
+        Some("repl") => return repl(base_device),
+        Some("diff") => {
+            let expected = match args.get(2).map_or(Ok(Expected::Add), |arg| arg.parse()) {
+                Ok(expected) => expected,
+                Err(err) => {
+                    println!("{}\nusage: day_24 diff [add|and|or]", err);
+                    return;
+                }
+            };
+            match base_device.compile() {
+                Ok(circuit) => print!("{}", circuit.diff(expected)),
+                Err(err) => println!("can't evaluate the circuit: {}", err),
+            }
+            return;
+        }
+        Some("dot") => {
+            // the wires a repair would swap are the ones worth looking at
+            let suspects = base_device.clone().repair_adder().unwrap_or_default();
+            print!("{}", base_device.to_dot(&suspects));
+            return;
+        }
+        Some("netlist") => {
+            print!("{}", base_device.to_netlist());
+            return;
+        }
+        Some("import") => {
+            let Some(path) = args.get(2) else {
+                println!("usage: day_24 import <netlist file>");
+                return;
+            };
+            let device = std::fs::read_to_string(path).map_err(|err| err.to_string())
+                .and_then(|netlist| Device::from_netlist(&netlist));
+            match device {
+                Ok(device) => {
+                    println!("{} gates, {} x bits, {} z bits", device.ops_map.len(), device.bits("x").len(), device.bits("z").len());
+                    match device.compile() {
+                        Ok(circuit) => print!("{}", circuit.diff(Expected::Add)),
+                        Err(err) => println!("can't evaluate the circuit: {}", err),
+                    }
+                }
+                Err(err) => println!("{}: {}", path, err),
+            }
+            return;
+        }
+        _ => {}
+    }
+    match base_device.eval() {
+        Ok(n) => println!("Part 1: {:?}", n),
+        Err(err) => println!("Part 1: can't evaluate the circuit: {}", err),
+    }
+    match base_device.repair_adder() {
+        Ok(swapped) => println!("Part 2: {}", swapped.join(",")),
+        Err(err) => println!("Part 2: not a repairable ripple-carry adder: {}", err),
+    }
 }
\ No newline at end of file
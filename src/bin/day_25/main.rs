@@ -1,4 +1,5 @@
 use itertools::Itertools;
+use advent_of_code_2024::lint;
 
 
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -76,8 +77,32 @@ impl From<&str> for Schematics {
 }
 
 
+fn lint(input: &str) -> lint::Report {
+    let schematics = input.split("\n\n").collect_vec();
+    let shape = |s: &str| (s.lines().count(), s.lines().map(|l| l.len()).max().unwrap_or(0));
+    let expected = schematics.first().map_or((0, 0), |s| shape(s));
+    let mut report = lint::Report::new();
+    report
+        .check("schematics only contain '#' and '.'", lint::unexpected_chars(input, "#."))
+        .check("every schematic has the same height and width", schematics.iter().enumerate()
+            .filter(|(_, s)| shape(s) != expected || !lint::ragged_lines(s).is_empty())
+            .map(|(i, s)| format!("schematic {} is {:?}, expected {:?} (height, width)", i + 1, shape(s), expected))
+            .collect())
+        .check("every schematic is a lock (full top row) or a key (full bottom row)", schematics.iter().enumerate()
+            .filter(|(_, s)| {
+                let full = |line: Option<&str>| line.is_some_and(|l| l.chars().all(|c| c == '#'));
+                full(s.lines().next()) == full(s.lines().last())
+            })
+            .map(|(i, _)| format!("schematic {} is neither a lock nor a key", i + 1))
+            .collect());
+    report
+}
+
 fn main() {
     let input = include_str!("input");
+    if std::env::args().nth(1).as_deref() == Some("lint") {
+        lint(input).finish();
+    }
     let schematics = Schematics::from(input);
     println!("Part 1: {:?}", schematics.count_fits());
 }
\ No newline at end of file
@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
-use advent_of_code_2024::Vec2D;
+use advent_of_code_2024::bench::{time, XorShift};
+use advent_of_code_2024::{lint, par, Vec2D};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Path {
@@ -95,15 +96,17 @@ impl Lab {
         self.seen.entry(self.guard.pos.clone()).or_default().insert(self.guard.dir.clone());
     }
 
-    fn solve(&mut self) -> Solution {
+    // Walks the guard off the map, marking what it sees, and returns each
+    // cell an obstruction could go on with the guard about to step into it.
+    fn walk(&mut self) -> Vec<(Vec2D, Guard)> {
+        // An obstruction on a cell the guard hasn't walked yet leaves the path
+        // up to it untouched, so each candidate can be checked on its own,
+        // starting from where the guard would first bump into it.
+        let mut candidates = vec![];
         loop {
             let next_pos = self.guard.next_pos();
             if Some(&Path::Clear) == self.map.get(&next_pos) && !self.seen.contains_key(&next_pos) {
-                let mut cloned_lab = self.clone();
-                cloned_lab.set_path(next_pos, Path::Obstacle);
-                if cloned_lab.has_loop() {
-                    self.possible_obstruction_positions.insert(next_pos);
-                }
+                candidates.push((next_pos, self.guard.clone()));
             }
 
             self.mark_guard_pos_as_seen();
@@ -111,6 +114,30 @@ impl Lab {
                 break;
             }
         }
+        candidates
+    }
+
+    // The original check, walking a copy of the lab cell by cell. Kept to
+    // compare against the jump table.
+    fn loops_by_walking(&self, candidates: &[(Vec2D, Guard)]) -> Vec<bool> {
+        par::map(candidates, |(pos, guard)| {
+            let mut cloned_lab = Lab { map: self.map.clone(), guard: guard.clone(), ..Lab::new() };
+            cloned_lab.set_path(*pos, Path::Obstacle);
+            cloned_lab.has_loop()
+        })
+    }
+
+    fn loops_by_jumping(&self, candidates: &[(Vec2D, Guard)]) -> Vec<bool> {
+        let jumps = JumpTable::from(self);
+        par::map(candidates, |(pos, guard)| jumps.has_loop(guard, *pos))
+    }
+
+    fn solve(&mut self) -> Solution {
+        let candidates = self.walk();
+        let has_loop = self.loops_by_jumping(&candidates);
+        self.possible_obstruction_positions.extend(
+            candidates.iter().zip(has_loop).filter(|(_, has_loop)| *has_loop).map(|((pos, _), _)| *pos)
+        );
         Solution {
             distinct_visited_positions: self.seen.len(),
             possible_obstruction_positions: self.possible_obstruction_positions.len(),
@@ -119,6 +146,78 @@ impl Lab {
 
 }
 
+const DIRECTIONS: [Vec2D; 4] = [Vec2D { x: 0, y: -1 }, Vec2D { x: 1, y: 0 }, Vec2D { x: 0, y: 1 }, Vec2D { x: -1, y: 0 }];
+
+// For every cell and direction, where a guard walking that way stops in front
+// of an obstacle, or `None` if it walks off the map. A loop check then only
+// visits the turning points, and the one extra obstruction is checked against
+// each stretch as it's walked rather than written into a copy of the map.
+struct JumpTable {
+    width: i64,
+    // indexed by y * width + x, then by position in `DIRECTIONS`
+    stops: Vec<[Option<Vec2D>; 4]>,
+}
+
+impl From<&Lab> for JumpTable {
+    fn from(lab: &Lab) -> Self {
+        let width = lab.map.keys().map(|pos| pos.x + 1).max().unwrap_or(0);
+        let height = lab.map.keys().map(|pos| pos.y + 1).max().unwrap_or(0);
+        let cells = (0..height).flat_map(|y| (0..width).map(move |x| Vec2D::new(x, y))).collect::<Vec<_>>();
+        let obstacles = cells.iter().map(|pos| lab.map.get(pos) == Some(&Path::Obstacle)).collect::<Vec<_>>();
+        let mut table = JumpTable { width, stops: vec![[None; 4]; cells.len()] };
+        for (d, dir) in DIRECTIONS.iter().enumerate() {
+            // fill in from the far side, so the cell ahead is always done:
+            // up and left look back along the rows, down and right forward
+            let order: Box<dyn Iterator<Item = usize>> = if dir.x + dir.y < 0 {
+                Box::new(0..cells.len())
+            } else {
+                Box::new((0..cells.len()).rev())
+            };
+            for i in order {
+                let ahead = cells[i] + *dir;
+                if !(0..width).contains(&ahead.x) || !(0..height).contains(&ahead.y) {
+                    continue;
+                }
+                let j = table.index(ahead);
+                table.stops[i][d] = if obstacles[j] { Some(cells[i]) } else { table.stops[j][d] };
+            }
+        }
+        table
+    }
+}
+
+impl JumpTable {
+    fn index(&self, pos: Vec2D) -> usize {
+        (pos.y * self.width + pos.x) as usize
+    }
+
+    fn has_loop(&self, guard: &Guard, obstacle: Vec2D) -> bool {
+        let mut pos = guard.pos;
+        let mut d = DIRECTIONS.iter().position(|dir| *dir == guard.dir).unwrap();
+        let mut turns = HashSet::new();
+        loop {
+            let dir = DIRECTIONS[d];
+            let mut stop = self.stops[self.index(pos)][d];
+            // the obstruction cuts the stretch short if it's in line ahead and
+            // nearer than wherever the guard would stop anyway
+            let to = obstacle - pos;
+            let distance = to.x * dir.x + to.y * dir.y;
+            let in_line = to.x * dir.y == to.y * dir.x;
+            if in_line && distance > 0 && stop.is_none_or(|stop| distance <= (stop - pos).x * dir.x + (stop - pos).y * dir.y) {
+                stop = Some(pos + dir * (distance - 1));
+            }
+            let Some(stop) = stop else {
+                return false;
+            };
+            if !turns.insert((stop, d)) {
+                return true;
+            }
+            pos = stop;
+            d = (d + 1) % 4;
+        }
+    }
+}
+
 impl From<&str> for Lab {
     fn from(value: &str) -> Self {
         let mut lab = Lab::new();
@@ -144,8 +243,79 @@ impl From<&str> for Lab {
     }
 }
 
+fn lint(input: &str) -> lint::Report {
+    let guards = input.lines().enumerate()
+        .flat_map(|(y, line)| line.match_indices('^').map(move |(x, _)| format!("{},{}", x, y)))
+        .collect::<Vec<_>>();
+    let mut report = lint::Report::new();
+    report
+        .check("map only contains '.', '#' and '^'", lint::unexpected_chars(input, ".#^"))
+        .check("map is rectangular", lint::ragged_lines(input))
+        .check("there is exactly one '^' guard", match guards.len() {
+            1 => vec![],
+            0 => vec!["no guard found".to_string()],
+            _ => vec![format!("{} guards found at {}", guards.len(), guards.join(" "))],
+        });
+    report
+}
+
+// A `size` x `size` lab with obstacles scattered from a fixed xorshift seed
+// about one cell in thirty, and the guard in the middle.
+fn generate(size: usize, seed: u64) -> String {
+    let mut rng = XorShift::new(seed);
+    let mut map = String::new();
+    for y in 0..size {
+        for x in 0..size {
+            let roll = rng.next_u64() % 100;
+            map.push(if (x, y) == (size / 2, size / 2) { '^' } else if roll < 3 { '#' } else { '.' });
+        }
+        map.push('\n');
+    }
+    map
+}
+
+// Times both loop checks on the puzzle input, then on a batch of generated
+// 130 x 130 labs, where most guards wander off the map quickly.
+fn bench(input: &str) {
+    const LABS: usize = 50;
+    let batches = [
+        ("input".to_string(), vec![input.to_string()]),
+        (format!("{} generated 130x130 labs", LABS), (1..).map(|seed| generate(130, seed)).filter(|map| {
+            // puzzle inputs always let the guard out, which `walk` relies on
+            let lab = Lab::from(map.as_str());
+            !JumpTable::from(&lab).has_loop(&lab.guard, Vec2D::new(-1, -1))
+        }).take(LABS).collect()),
+    ];
+    for (name, maps) in batches {
+        let labs = maps.iter().map(|map| {
+            let mut lab = Lab::from(map.as_str());
+            let candidates = lab.walk();
+            (lab, candidates)
+        }).collect::<Vec<_>>();
+        let walked = time(&format!("{}, walking cells", name), || {
+            labs.iter().map(|(lab, candidates)| lab.loops_by_walking(candidates)).collect::<Vec<_>>()
+        });
+        let jumped = time(&format!("{}, jump table", name), || {
+            labs.iter().map(|(lab, candidates)| lab.loops_by_jumping(candidates)).collect::<Vec<_>>()
+        });
+        assert_eq!(walked, jumped, "loop checks disagree on {}", name);
+        println!(
+            "{} candidates, {} loops",
+            jumped.iter().map(Vec::len).sum::<usize>(),
+            jumped.iter().flatten().filter(|&&has_loop| has_loop).count(),
+        );
+    }
+}
+
 fn main() {
     let input = include_str!("input");
+    if std::env::args().nth(1).as_deref() == Some("lint") {
+        lint(input).finish();
+    }
+    if std::env::args().nth(1).as_deref() == Some("bench") {
+        bench(input);
+        return;
+    }
     let mut lab: Lab = input.into();
     let Solution {
         distinct_visited_positions,
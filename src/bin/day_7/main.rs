@@ -1,4 +1,5 @@
 use itertools::{Itertools, repeat_n};
+use advent_of_code_2024::par;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 enum Operator {
@@ -62,15 +63,13 @@ struct Calibration {
 
 impl Calibration {
     fn sum_solvable_equations(&self, ops: &[Operator]) -> usize {
-        self.equations.iter().filter_map(
-            |equation| {
-                if equation.solve(ops.into_iter()) {
-                    Some(equation.test_value)
-                } else {
-                    None
-                }
+        par::map(&self.equations, |equation| {
+            if equation.solve(ops.iter()) {
+                Some(equation.test_value)
+            } else {
+                None
             }
-        ).sum()
+        }).into_iter().flatten().sum()
     }
 }
 
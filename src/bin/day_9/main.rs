@@ -1,4 +1,9 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
 use std::fmt::Display;
+use itertools::Itertools;
+use advent_of_code_2024::bench::{time, XorShift};
+use advent_of_code_2024::{lint, svg};
 
 enum DiskMapEntry {
     File {
@@ -14,19 +19,35 @@ struct DiskMap {
     entries: Vec<DiskMapEntry>,
 }
 
-impl From<&str> for DiskMap {
-    fn from(value: &str) -> Self {
-        DiskMap {
-            entries: value
-                .chars()
-                .enumerate()
-                .map(|(i, c)| {
-                    match i % 2 {
-                        0 => DiskMapEntry::File { id: i / 2, size: c.to_digit(10).unwrap() as usize },
-                        _ => DiskMapEntry::Free { size: c.to_digit(10).unwrap() as usize },
-                    }
-                }).collect()
+// A puzzle disk map numbers its files by position. A serialized layout may
+// have them in any order, or split in pieces, so it can follow the digits
+// with `:` and the id of each file entry, comma separated.
+impl TryFrom<&str> for DiskMap {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (digits, ids) = match value.split_once(':') {
+            Some((digits, ids)) => (digits, Some(ids.split(',')
+                .map(|id| id.trim().parse().map_err(|_| format!("invalid file id {:?}", id)))
+                .collect::<Result<Vec<usize>, _>>()?)),
+            None => (value, None),
+        };
+        let files = digits.len().div_ceil(2);
+        if let Some(ids) = ids.as_ref().filter(|ids| ids.len() != files) {
+            return Err(format!("{} file ids for {} file entries", ids.len(), files));
         }
+        let entries = digits
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let size = c.to_digit(10).ok_or(format!("unexpected {:?} at offset {}", c, i))? as usize;
+                Ok(match i % 2 {
+                    0 => DiskMapEntry::File { id: ids.as_ref().map_or(i / 2, |ids| ids[i / 2]), size },
+                    _ => DiskMapEntry::Free { size },
+                })
+            })
+            .collect::<Result<_, String>>()?;
+        Ok(DiskMap { entries })
     }
 }
 
@@ -49,16 +70,129 @@ struct DiskLayout {
     used_space: usize,
 }
 
+// The longest span a single disk map digit can describe.
+const MAX_SPAN: usize = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum CompactionStrategy {
+    // move single blocks from the end into the leftmost free block
     Blocks,
+    // move whole files into the leftmost free span that fits
     Groups,
+    // ... the smallest free span that fits
+    BestFit,
+    // ... the largest free span
+    WorstFit,
+    // whole files where they fit, otherwise as many blocks as will go left
+    Hybrid,
+    // pack every file left, keeping them in order
+    Defrag,
+}
+
+impl CompactionStrategy {
+    const ALL: [CompactionStrategy; 6] = [
+        CompactionStrategy::Blocks,
+        CompactionStrategy::Groups,
+        CompactionStrategy::BestFit,
+        CompactionStrategy::WorstFit,
+        CompactionStrategy::Hybrid,
+        CompactionStrategy::Defrag,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            CompactionStrategy::Blocks => "blocks",
+            CompactionStrategy::Groups => "first-fit",
+            CompactionStrategy::BestFit => "best-fit",
+            CompactionStrategy::WorstFit => "worst-fit",
+            CompactionStrategy::Hybrid => "hybrid",
+            CompactionStrategy::Defrag => "defrag",
+        }
+    }
+}
+
+fn first_fit(spans: &[(usize, usize)]) -> Option<usize> {
+    spans.first().map(|&(j, _)| j)
+}
+
+fn best_fit(spans: &[(usize, usize)]) -> Option<usize> {
+    spans.iter().min_by_key(|&&(j, size)| (size, j)).map(|&(j, _)| j)
+}
+
+fn worst_fit(spans: &[(usize, usize)]) -> Option<usize> {
+    spans.iter().max_by_key(|&&(j, size)| (size, Reverse(j))).map(|&(j, _)| j)
+}
+
+// What a compaction did, worked out by comparing the blocks before and after.
+struct CompactionReport {
+    strategy: CompactionStrategy,
+    files_moved: usize,
+    fragments_created: usize,
+    // sizes of the free spans left between files, and how many of each
+    holes: BTreeMap<usize, usize>,
+    free_tail: usize,
+    checksum: usize,
+}
+
+impl Display for CompactionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{}", self.strategy.name())?;
+        writeln!(f, "  files moved:       {}", self.files_moved)?;
+        writeln!(f, "  fragments created: {}", self.fragments_created)?;
+        let holes = self.holes.iter().map(|(size, count)| format!("{} of {}", count, size)).join(", ");
+        writeln!(f, "  free spans:        {}", if holes.is_empty() { "none".to_string() } else { holes })?;
+        writeln!(f, "  free at the end:   {}", self.free_tail)?;
+        writeln!(f, "  checksum:          {}", self.checksum)
+    }
 }
 
 impl DiskLayout {
     fn compact(&mut self, strategy: CompactionStrategy) {
         match strategy {
             CompactionStrategy::Blocks => self.compact_blocks(),
-            CompactionStrategy::Groups => self.compact_groups(),
+            CompactionStrategy::Groups => self.compact_first_fit(),
+            CompactionStrategy::BestFit => self.compact_files(best_fit, false),
+            CompactionStrategy::WorstFit => self.compact_files(worst_fit, false),
+            CompactionStrategy::Hybrid => self.compact_files(first_fit, true),
+            CompactionStrategy::Defrag => self.defrag(),
+        }
+    }
+
+    fn compact_with_report(&mut self, strategy: CompactionStrategy) -> CompactionReport {
+        let before = self.clone();
+        self.compact(strategy);
+        self.report(&before, strategy)
+    }
+
+    fn file_positions(&self) -> HashMap<usize, Vec<usize>> {
+        let mut positions: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (i, block) in self.blocks.iter().enumerate() {
+            if let DiskBlock::File(id) = block {
+                positions.entry(*id).or_default().push(i);
+            }
+        }
+        positions
+    }
+
+    fn report(&self, before: &DiskLayout, strategy: CompactionStrategy) -> CompactionReport {
+        let (old, new) = (before.file_positions(), self.file_positions());
+        let files_moved = new.iter().filter(|(id, positions)| old.get(id) != Some(positions)).count();
+        let fragments_created = new.values()
+            .map(|positions| positions.iter().tuple_windows().filter(|(a, b)| *b - *a > 1).count())
+            .sum();
+        let spans = self.blocks.iter().chunk_by(|block| **block == DiskBlock::Free);
+        let mut spans = spans.into_iter()
+            .filter(|(free, _)| *free)
+            .map(|(_, span)| span.count())
+            .collect_vec();
+        let free_tail = if self.blocks.last() == Some(&DiskBlock::Free) { spans.pop().unwrap() } else { 0 };
+        CompactionReport {
+            strategy,
+            files_moved,
+            fragments_created,
+            holes: spans.into_iter().counts().into_iter().collect(),
+            free_tail,
+            checksum: self.checksum(),
         }
     }
 
@@ -74,25 +208,140 @@ impl DiskLayout {
         }
     }
 
-    fn compact_groups(&mut self) {
-        let mut last_moved_file_id = usize::MAX;
-        for i in (0..self.groups.len()).rev() {
-            if let DiskBlockGroup { block: DiskBlock::File(file_id), size: file_size } = self.groups[i] {
-                if file_id < last_moved_file_id {
-                    last_moved_file_id = file_id;
-                    for j in 0..i {
-                        let test_group = &mut self.groups[j];
-                        if test_group.block == DiskBlock::Free && test_group.size >= file_size {
-                            test_group.size -= file_size;
-                            self.groups.insert(j, DiskBlockGroup { block: DiskBlock::Free, size: file_size });
-                            self.groups.swap(i + 1, j);
-                            break;
-                        }
-                    }
-                }
+    // The same moves as `compact_files(first_fit, false)`, on any layout,
+    // without rescanning or shifting `groups` for every file. Free spans sit
+    // in one min-heap of start offsets per length up to the largest file, and
+    // a file's leftmost fit is the smallest top among the heaps for its size
+    // and up. Any longer span fits every file, so those share one overflow
+    // heap of (start, length) and compacted layouts with long spans don't
+    // mean more heaps to look through. Space a file leaves behind is never
+    // indexed: only files further left move after it.
+    fn compact_first_fit(&mut self) {
+        let largest = self.groups.iter().filter(|group| group.block != DiskBlock::Free).map(|group| group.size).max();
+        let mut free: Vec<BinaryHeap<Reverse<usize>>> = vec![BinaryHeap::new(); largest.unwrap_or(0) + 1];
+        let mut overflow: BinaryHeap<Reverse<(usize, usize)>> = BinaryHeap::new();
+        fn add_span(free: &mut [BinaryHeap<Reverse<usize>>], overflow: &mut BinaryHeap<Reverse<(usize, usize)>>, at: usize, length: usize) {
+            match free.get_mut(length) {
+                Some(heap) => heap.push(Reverse(at)),
+                None => overflow.push(Reverse((at, length))),
+            }
+        }
+        let mut files = vec![];
+        let mut start = 0;
+        for group in self.groups.iter() {
+            match group.block {
+                DiskBlock::File(id) => files.push((id, start, group.size)),
+                DiskBlock::Free if group.size > 0 => add_span(&mut free, &mut overflow, start, group.size),
+                DiskBlock::Free => {}
+            }
+            start += group.size;
+        }
+        let total = start;
+
+        // Like `compact_files`, go by id, highest first, each time taking the
+        // rightmost piece of the file left of the last one looked at.
+        let mut order = (0..files.len()).collect_vec();
+        order.sort_by_key(|&f| Reverse((files[f].0, files[f].1)));
+        let mut bound = usize::MAX;
+        let mut last_id = None;
+        for f in order {
+            let (id, start, size) = files[f];
+            if last_id == Some(id) || start >= bound {
+                continue;
+            }
+            (last_id, bound) = (Some(id), start);
+            if size == 0 {
+                continue;
+            }
+            let Some((at, length)) = (size..free.len())
+                .filter_map(|length| free[length].peek().map(|Reverse(at)| (*at, length)))
+                .chain(overflow.peek().map(|Reverse(span)| *span))
+                .filter(|&(at, _)| at < start)
+                .min() else {
+                continue;
+            };
+            if length < free.len() {
+                free[length].pop();
+            } else {
+                overflow.pop();
+            }
+            if length > size {
+                add_span(&mut free, &mut overflow, at + size, length - size);
+            }
+            files[f].1 = at;
+        }
+
+        self.blocks = vec![DiskBlock::Free; total];
+        for &(id, start, size) in files.iter() {
+            self.blocks[start..start + size].fill(DiskBlock::File(id));
+        }
+        self.groups = self.blocks.iter()
+            .chunk_by(|block| (*block).clone())
+            .into_iter()
+            .map(|(block, run)| DiskBlockGroup { block, size: run.count() })
+            .collect();
+    }
+
+    // Moves each file, highest id first, into the free span `pick` chooses
+    // from the (index, size) of those to its left that are big enough. With
+    // `fragment`, a file with nowhere to go whole is split across the
+    // leftmost free spans instead, moving blocks off its end.
+    fn compact_files(&mut self, pick: fn(&[(usize, usize)]) -> Option<usize>, fragment: bool) {
+        let mut i = self.groups.len();
+        let max_id = self.groups.iter().filter_map(|group| match group.block {
+            DiskBlock::File(id) => Some(id),
+            DiskBlock::Free => None,
+        }).max();
+        for id in (0..=max_id.unwrap_or(0)).rev() {
+            // files only ever move left, so the next one is left of the last
+            let Some(found) = self.groups[..i].iter().rposition(|group| group.block == DiskBlock::File(id)) else {
+                continue;
+            };
+            i = found;
+            let file_size = self.groups[i].size;
+            let spans = self.groups[..i].iter().enumerate()
+                .filter(|(_, group)| group.block == DiskBlock::Free && group.size >= file_size && file_size > 0)
+                .map(|(j, group)| (j, group.size))
+                .collect_vec();
+            if let Some(j) = pick(&spans) {
+                self.groups[i].block = DiskBlock::Free;
+                self.groups[j].size -= file_size;
+                self.groups.insert(j, DiskBlockGroup { block: DiskBlock::File(id), size: file_size });
+                // the insert shifted the file's old slot along one
+                i += 1;
+            } else if fragment {
+                i = self.fragment_file(i);
+            }
+        }
+        self.rebuild_blocks_from_groups();
+    }
+
+    // Returns where the file's group, or what's left of it, ends up.
+    fn fragment_file(&mut self, mut i: usize) -> usize {
+        let DiskBlockGroup { block, size } = self.groups[i].clone();
+        let mut remaining = size;
+        let mut j = 0;
+        while j < i && remaining > 0 {
+            if self.groups[j].block == DiskBlock::Free && self.groups[j].size > 0 {
+                let moved = remaining.min(self.groups[j].size);
+                self.groups[j].size -= moved;
+                self.groups.insert(j, DiskBlockGroup { block: block.clone(), size: moved });
+                remaining -= moved;
+                i += 1;
             }
+            j += 1;
+        }
+        if remaining < size {
+            self.groups[i].size = remaining;
+            self.groups.insert(i + 1, DiskBlockGroup { block: DiskBlock::Free, size: size - remaining });
         }
+        i
+    }
 
+    fn defrag(&mut self) {
+        let free = self.groups.iter().filter(|group| group.block == DiskBlock::Free).map(|group| group.size).sum();
+        self.groups.retain(|group| group.block != DiskBlock::Free);
+        self.groups.push(DiskBlockGroup { block: DiskBlock::Free, size: free });
         self.rebuild_blocks_from_groups();
     }
 
@@ -103,6 +352,63 @@ impl DiskLayout {
             .collect();
     }
 
+    // The layout as a disk map that parses back to the same blocks. Runs
+    // longer than a digit are split with empty entries of the other kind in
+    // between. Unless the files are just 0, 1, 2, ... as in a puzzle input,
+    // the digits are followed by `:` and the id of every file entry, e.g.
+    // `2031:0,5,2`; an empty file entry takes the id of the file before it
+    // (0 at the start) so the list stays one id per file entry.
+    fn to_disk_map(&self) -> String {
+        let mut digits = String::new();
+        let mut ids = vec![];
+        let mut last_id = 0;
+        for (block, run) in self.blocks.iter().chunk_by(|block| (*block).clone()).into_iter() {
+            let mut size = run.count();
+            let id = match block {
+                DiskBlock::File(id) => id,
+                DiskBlock::Free => last_id,
+            };
+            let want_file = block != DiskBlock::Free;
+            loop {
+                // entries alternate file, free, so the next one is a file
+                // when the count so far is even
+                let next_is_file = digits.len().is_multiple_of(2);
+                if next_is_file != want_file {
+                    if next_is_file {
+                        ids.push(last_id);
+                    }
+                    digits.push('0');
+                }
+                let piece = size.min(MAX_SPAN);
+                if want_file {
+                    ids.push(id);
+                }
+                digits.push(char::from_digit(piece as u32, 10).unwrap());
+                size -= piece;
+                if size == 0 {
+                    break;
+                }
+            }
+            last_id = id;
+        }
+        if ids.iter().copied().eq(0..ids.len()) {
+            digits
+        } else {
+            format!("{}:{}", digits, ids.iter().join(","))
+        }
+    }
+
+    // Blocks wrap onto rows of 64, coloured by file id.
+    fn render(&self) -> String {
+        let width = 64;
+        svg::grid(width, self.blocks.len().div_ceil(width), |x, y| {
+            match self.blocks.get(y * width + x) {
+                Some(DiskBlock::File(id)) => Some(svg::palette(*id)),
+                _ => None,
+            }
+        })
+    }
+
     fn checksum(&self) -> usize {
         self.blocks.iter().enumerate()
             .filter_map(|(i, block)| {
@@ -114,47 +420,212 @@ impl DiskLayout {
     }
 }
 
+// One entry per block, space separated so ids past 9 can't run together.
 impl Display for DiskLayout {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for block in self.blocks.iter() {
-            write!(f, "{}", match block {
-                DiskBlock::File(id) => id.to_string(),
-                DiskBlock::Free => ".".to_string(),
-            })?;
-        }
-        Ok(())
+        let blocks = self.blocks.iter().map(|block| match block {
+            DiskBlock::File(id) => id.to_string(),
+            DiskBlock::Free => ".".to_string(),
+        });
+        write!(f, "{}", blocks.format(" "))
     }
 }
 
+// Empty entries and runs split across entries only exist to fit the
+// digits, so they're dropped and merged back here. Otherwise a re-parsed
+// layout would hold pieces of one file that the compactors treat as
+// separate files.
 impl From<DiskMap> for DiskLayout {
     fn from(value: DiskMap) -> Self {
         let mut used_space = 0;
-        let mut blocks: Vec<DiskBlock> = Vec::new();
         let mut groups: Vec<DiskBlockGroup> = Vec::new();
         for entry in value.entries {
-            match entry {
+            let (block, size) = match entry {
                 DiskMapEntry::File { id, size } => {
                     used_space += size;
-                    groups.push(DiskBlockGroup { block: DiskBlock::File(id), size });
-                    blocks.extend(std::iter::repeat(DiskBlock::File(id)).take(size));
-                }
-                DiskMapEntry::Free { size } => {
-                    groups.push(DiskBlockGroup { block: DiskBlock::Free, size });
-                    blocks.extend(std::iter::repeat(DiskBlock::Free).take(size));
+                    (DiskBlock::File(id), size)
                 }
+                DiskMapEntry::Free { size } => (DiskBlock::Free, size),
+            };
+            match groups.last_mut() {
+                _ if size == 0 => {}
+                Some(last) if last.block == block => last.size += size,
+                _ => groups.push(DiskBlockGroup { block, size }),
             }
         }
-        DiskLayout { blocks, groups, used_space }
+        let mut layout = DiskLayout { blocks: vec![], groups, used_space };
+        layout.rebuild_blocks_from_groups();
+        layout
+    }
+}
+
+fn lint(input: &str) -> lint::Report {
+    let mut report = lint::Report::new();
+    report
+        .check("disk map only contains digits", input.char_indices()
+            .filter(|(_, c)| !c.is_ascii_digit())
+            .map(|(i, c)| format!("unexpected {:?} at offset {}", c, i))
+            .collect())
+        .check("disk map has odd length, ending with a file", if input.len() % 2 == 1 {
+            vec![]
+        } else {
+            vec![format!("{} entries, the last one is a free span", input.len())]
+        });
+    report
+}
+
+// A random disk map of `length` digits, from a fixed xorshift seed so runs
+// can be compared.
+fn generate(length: usize, seed: u64) -> String {
+    let mut rng = XorShift::new(seed);
+    (0..length).map(|i| {
+        let roll = rng.next_u64();
+        // files are never empty in real inputs
+        let lowest = if i % 2 == 0 { 1 } else { 0 };
+        char::from_digit((lowest + roll % (10 - lowest)) as u32, 10).unwrap()
+    }).collect()
+}
+
+// Times whole-file compaction by rescanning the groups against the free span
+// heaps, on generated disk maps of growing length. Both are also run again
+// on layouts other strategies have already compacted, where spans and files
+// can be longer than a digit and out of id order, to check they still agree.
+fn bench(lengths: &[usize]) {
+    for &length in lengths {
+        let layout: DiskLayout = DiskMap::try_from(generate(length, 0x9e3779b97f4a7c15).as_str()).unwrap().into();
+        let mut scanned = layout.clone();
+        time(&format!("{} digits, scanning groups", length), || scanned.compact_files(first_fit, false));
+        let mut indexed = layout.clone();
+        time(&format!("{} digits, free span heaps", length), || indexed.compact_first_fit());
+        assert_eq!(scanned.checksum(), indexed.checksum(), "checksums differ on {} digits", length);
+
+        for strategy in [CompactionStrategy::Groups, CompactionStrategy::WorstFit, CompactionStrategy::Hybrid, CompactionStrategy::Defrag] {
+            let mut compacted = layout.clone();
+            compacted.compact(strategy);
+            let (mut scanned, mut indexed) = (compacted.clone(), compacted);
+            scanned.compact_files(first_fit, false);
+            indexed.compact_first_fit();
+            assert_eq!(scanned.blocks, indexed.blocks, "layouts differ on {} digits after {}", length, strategy.name());
+        }
     }
 }
 
 fn main() {
     let input = include_str!("input");
-    let disk_map: DiskMap = input.into();
+    if std::env::args().nth(1).as_deref() == Some("lint") {
+        lint(input.trim_end()).finish();
+    }
+    if std::env::args().nth(1).as_deref() == Some("bench") {
+        let Ok(lengths) = std::env::args().skip(2).map(|length| length.parse()).collect::<Result<Vec<usize>, _>>() else {
+            println!("usage: day_9 bench [digits...]");
+            return;
+        };
+        bench(if lengths.is_empty() { &[1_000, 10_000, 50_000] } else { &lengths });
+        return;
+    }
+    let disk_map = match DiskMap::try_from(input.trim_end()) {
+        Ok(disk_map) => disk_map,
+        Err(err) => {
+            println!("invalid disk map: {}", err);
+            return;
+        }
+    };
     let mut disk_layout: DiskLayout = disk_map.into();
+    if std::env::args().nth(1).as_deref() == Some("render") {
+        disk_layout.compact(CompactionStrategy::Groups);
+        print!("{}", disk_layout.render());
+        return;
+    }
+    if std::env::args().nth(1).as_deref() == Some("compare") {
+        for strategy in CompactionStrategy::ALL {
+            let mut layout = disk_layout.clone();
+            print!("{}", layout.compact_with_report(strategy));
+        }
+        return;
+    }
+    if std::env::args().nth(1).as_deref() == Some("roundtrip") {
+        // serialize every strategy's result and check it parses back
+        for strategy in CompactionStrategy::ALL {
+            let mut layout = disk_layout.clone();
+            layout.compact(strategy);
+            let serialized = layout.to_disk_map();
+            let status = match DiskMap::try_from(serialized.as_str()).map(DiskLayout::from) {
+                Ok(parsed) if parsed.blocks == layout.blocks && parsed.checksum() == layout.checksum() => "ok".to_string(),
+                Ok(_) => "MISMATCH".to_string(),
+                Err(err) => format!("UNREADABLE ({})", err),
+            };
+            println!("{:<10} {:<8} {} chars", strategy.name(), status, serialized.len());
+        }
+        return;
+    }
+    if let Some(command @ ("show" | "serialize")) = std::env::args().nth(1).as_deref() {
+        let strategy = std::env::args().nth(2)
+            .and_then(|name| CompactionStrategy::ALL.into_iter().find(|strategy| strategy.name() == name));
+        let Some(strategy) = strategy else {
+            println!("usage: day_9 {} <{}>", command, CompactionStrategy::ALL.iter().map(|strategy| strategy.name()).join("|"));
+            return;
+        };
+        disk_layout.compact(strategy);
+        if command == "show" {
+            println!("{}", disk_layout);
+        } else {
+            println!("{}", disk_layout.to_disk_map());
+        }
+        return;
+    }
     disk_layout.compact(CompactionStrategy::Blocks);
     println!("Part 1: {}", disk_layout.checksum());
     disk_layout.rebuild_blocks_from_groups();
     disk_layout.compact(CompactionStrategy::Groups);
     println!("Part 2: {}", disk_layout.checksum());
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout(groups: &[(Option<usize>, usize)]) -> DiskLayout {
+        let groups = groups.iter().map(|&(id, size)| DiskBlockGroup {
+            block: id.map_or(DiskBlock::Free, DiskBlock::File),
+            size,
+        }).collect_vec();
+        let used_space = groups.iter().filter(|group| group.block != DiskBlock::Free).map(|group| group.size).sum();
+        let mut layout = DiskLayout { blocks: vec![], groups, used_space };
+        layout.rebuild_blocks_from_groups();
+        layout
+    }
+
+    fn reparsed(layout: &DiskLayout) -> DiskLayout {
+        DiskMap::try_from(layout.to_disk_map().as_str()).unwrap().into()
+    }
+
+    #[test]
+    fn reparsed_layouts_compact_the_same() {
+        let layouts = [
+            // an empty entry carrying file 2's id ends up right of it
+            layout(&[(Some(0), 1), (None, 3), (Some(2), 2), (None, 12), (Some(1), 1)]),
+            // file 1 is too long for one digit
+            layout(&[(Some(0), 2), (None, 14), (Some(1), 12)]),
+        ];
+        for original in layouts {
+            let copy = reparsed(&original);
+            assert_eq!(copy.blocks, original.blocks, "{}", original.to_disk_map());
+            for strategy in CompactionStrategy::ALL {
+                let (mut original, mut copy) = (original.clone(), copy.clone());
+                original.compact(strategy);
+                copy.compact(strategy);
+                assert_eq!(copy.blocks, original.blocks, "{} with {}", original.to_disk_map(), strategy.name());
+            }
+        }
+    }
+
+    #[test]
+    fn compacted_layouts_roundtrip() {
+        let map = "2333133121414131402";
+        for strategy in CompactionStrategy::ALL {
+            let mut layout: DiskLayout = DiskMap::try_from(map).unwrap().into();
+            layout.compact(strategy);
+            assert_eq!(reparsed(&layout).blocks, layout.blocks, "{}", strategy.name());
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+// How long a client gets to send its request before it's dropped, so one idle
+// connection can't hold up the single-threaded server.
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct Request {
+    pub method: String,
+    pub path: String,
+}
+
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    pub fn ok(content_type: &'static str, body: String) -> Self {
+        Response { status: 200, content_type, body }
+    }
+
+    pub fn not_found() -> Self {
+        Response { status: 404, content_type: "text/plain; charset=utf-8", body: "not found\n".to_string() }
+    }
+
+    pub fn error(message: String) -> Self {
+        Response { status: 500, content_type: "text/plain; charset=utf-8", body: message }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+// A deliberately tiny HTTP/1.1 server: one connection at a time, request line
+// and headers only, and the connection is closed after every response.
+pub fn serve<F: FnMut(&Request) -> Response>(listener: TcpListener, mut handler: F) {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        match handle(stream, &mut handler) {
+            Ok(()) => {}
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => eprintln!("error: {}", e),
+        }
+    }
+}
+
+fn handle<F: FnMut(&Request) -> Response>(mut stream: TcpStream, handler: &mut F) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut reader = BufReader::new(&stream);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // skip the headers, nothing here needs them
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let (method, path) = (parts.next(), parts.next());
+    let response = match (method, path) {
+        (Some(method), Some(path)) if method == "GET" || method == "HEAD" => {
+            handler(&Request { method: method.to_string(), path: path.to_string() })
+        }
+        (Some(_), Some(_)) => Response { status: 405, content_type: "text/plain", body: "method not allowed\n".to_string() },
+        _ => Response { status: 400, content_type: "text/plain", body: "bad request\n".to_string() },
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status, response.reason(), response.content_type, response.body.len(),
+    )?;
+    if method != Some("HEAD") {
+        stream.write_all(response.body.as_bytes())?;
+    }
+    stream.flush()
+}
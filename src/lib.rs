@@ -1,3 +1,11 @@
+pub mod bench;
+pub mod http;
+pub mod lint;
+pub mod log;
+pub mod par;
+pub mod repl;
+pub mod svg;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub struct Vec2D {
     pub x: i64,
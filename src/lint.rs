@@ -0,0 +1,75 @@
+use std::fmt;
+
+struct Check {
+    invariant: String,
+    violations: Vec<String>,
+}
+
+// The structural assumptions a day's solver makes about its input, each with
+// whatever violations were found. An empty violation list means it holds.
+#[derive(Default)]
+pub struct Report {
+    checks: Vec<Check>,
+}
+
+impl Report {
+    pub fn new() -> Self {
+        Report::default()
+    }
+
+    pub fn check(&mut self, invariant: &str, violations: Vec<String>) -> &mut Self {
+        self.checks.push(Check { invariant: invariant.to_string(), violations });
+        self
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(|check| check.violations.is_empty())
+    }
+
+    // Prints the report and exits, with a failure code if anything was violated.
+    pub fn finish(&self) -> ! {
+        print!("{}", self);
+        std::process::exit(if self.is_ok() { 0 } else { 1 })
+    }
+}
+
+// Violation lists can get long on a badly broken input, so only the first few
+// are shown.
+const MAX_SHOWN: usize = 10;
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in self.checks.iter() {
+            if check.violations.is_empty() {
+                writeln!(f, "ok    {}", check.invariant)?;
+                continue;
+            }
+            writeln!(f, "FAIL  {}", check.invariant)?;
+            for violation in check.violations.iter().take(MAX_SHOWN) {
+                writeln!(f, "      - {}", violation)?;
+            }
+            if check.violations.len() > MAX_SHOWN {
+                writeln!(f, "      ... and {} more", check.violations.len() - MAX_SHOWN)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// Reports every character of a grid input that isn't one of `allowed`.
+pub fn unexpected_chars(input: &str, allowed: &str) -> Vec<String> {
+    input.lines().enumerate()
+        .flat_map(|(y, line)| line.chars().enumerate().map(move |(x, c)| (x, y, c)))
+        .filter(|(_, _, c)| !allowed.contains(*c))
+        .map(|(x, y, c)| format!("unexpected {:?} at {},{}", c, x, y))
+        .collect()
+}
+
+// Reports lines of a grid input whose width differs from the first line's.
+pub fn ragged_lines(input: &str) -> Vec<String> {
+    let width = input.lines().next().map_or(0, |line| line.chars().count());
+    input.lines().enumerate()
+        .filter(|(_, line)| line.chars().count() != width)
+        .map(|(y, line)| format!("line {} is {} wide, expected {}", y + 1, line.chars().count(), width))
+        .collect()
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+use std::sync::LazyLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn parse(value: &str) -> Option<Level> {
+        match value.to_ascii_lowercase().as_str() {
+            "0" | "error" => Some(Level::Error),
+            "1" | "warn" => Some(Level::Warn),
+            "2" | "info" => Some(Level::Info),
+            "3" | "debug" => Some(Level::Debug),
+            "4" | "trace" => Some(Level::Trace),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Parsed from `AOC_LOG`, a comma separated list of `level` and `target=level`
+// entries, e.g. `debug,day_17=trace`. Targets are the day binaries' crate
+// names, which is what `module_path!()` expands to inside them. A target
+// covers itself and its `::` submodules, so `day_1` doesn't match `day_17`.
+#[derive(Debug)]
+struct Filter {
+    default: Level,
+    targets: Vec<(String, Level)>,
+}
+
+impl From<&str> for Filter {
+    fn from(value: &str) -> Self {
+        let mut filter = Filter { default: Level::Warn, targets: vec![] };
+        for entry in value.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            match entry.split_once('=') {
+                Some((target, level)) => match Level::parse(level) {
+                    Some(level) => filter.targets.push((target.to_string(), level)),
+                    None => eprintln!("[WARN log] ignoring invalid AOC_LOG entry: {}", entry),
+                },
+                None => match Level::parse(entry) {
+                    Some(level) => filter.default = level,
+                    None => eprintln!("[WARN log] ignoring invalid AOC_LOG entry: {}", entry),
+                },
+            }
+        }
+        filter
+    }
+}
+
+impl Filter {
+    fn level_for(&self, target: &str) -> Level {
+        self.targets.iter()
+            .rev()
+            .find(|(prefix, _)| target.strip_prefix(prefix.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::")))
+            .map_or(self.default, |(_, level)| *level)
+    }
+}
+
+static FILTER: LazyLock<Filter> = LazyLock::new(
+    || Filter::from(std::env::var("AOC_LOG").unwrap_or_default().as_str())
+);
+
+pub fn enabled(target: &str, level: Level) -> bool {
+    level <= level_for(target)
+}
+
+// The most verbose level `target` logs at. The macros look this up once per
+// call site and keep it, so logging in a hot loop costs a comparison.
+pub fn level_for(target: &str) -> Level {
+    FILTER.level_for(target)
+}
+
+// Log lines go to stderr so they never mix with the answers on stdout.
+pub fn log(target: &str, level: Level, args: fmt::Arguments) {
+    eprintln!("[{} {}] {}", level, target, args);
+}
+
+#[macro_export]
+macro_rules! log_at {
+    ($level:expr, $($arg:tt)+) => {{
+        static LEVEL: std::sync::OnceLock<$crate::log::Level> = std::sync::OnceLock::new();
+        if $level <= *LEVEL.get_or_init(|| $crate::log::level_for(module_path!())) {
+            $crate::log::log(module_path!(), $level, format_args!($($arg)+));
+        }
+    }};
+}
+
+#[macro_export]
+macro_rules! error {
+    ($($arg:tt)+) => { $crate::log_at!($crate::log::Level::Error, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)+) => { $crate::log_at!($crate::log::Level::Warn, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)+) => { $crate::log_at!($crate::log::Level::Info, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)+) => { $crate::log_at!($crate::log::Level::Debug, $($arg)+) };
+}
+
+#[macro_export]
+macro_rules! trace {
+    ($($arg:tt)+) => { $crate::log_at!($crate::log::Level::Trace, $($arg)+) };
+}
@@ -1,3 +1,356 @@
-fn main() {
-    println!("Advent of Code 2024!");
+use std::fmt::Write;
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Command, ExitCode};
+use std::time::{Duration, Instant};
+use advent_of_code_2024::http::{self, Request, Response};
+use advent_of_code_2024::par;
+
+const USAGE: &str = "\
+usage: aoc [-v|-vv] [--jobs N] <command>
+
+commands:
+  run [day...]  run the given days, or all of them
+  repl <day>    explore a day's parsed input interactively
+  lint [day...] check the input invariants the given days' solvers rely on
+  serve [port]  serve a dashboard of answers, timings and visualisations on
+                http://127.0.0.1:<port> (default 8024); answers are verified
+                against src/bin/day_N/answers when that file exists
+  accept [day...]
+                save the given days' current output as their answers, once
+                adventofcode.com has accepted it; like the inputs, answers
+                are personal and aren't checked in
+
+options:
+  -v, -vv       log solver internals of every day at debug / trace level
+                to stderr; AOC_LOG=day_18=trace,... sets the level for
+                single days, and on its own leaves the rest at warn
+  -j, --jobs N  run up to N days at once, and let each day use N threads
+                for its parallel loops (default: number of cores)";
+
+// Days whose binaries understand `repl`.
+const REPL_DAYS: &[u32] = &[15, 17, 24];
+
+// Days whose binaries understand `lint`.
+const LINT_DAYS: &[u32] = &[6, 9, 13, 20, 24, 25];
+
+// Days whose binaries understand `render`, printing an SVG.
+const RENDER_DAYS: &[u32] = &[9, 12, 14, 15, 16, 18];
+
+// Day binaries are built next to the runner, e.g. target/debug/day_24.
+fn day_binary(day: u32) -> Result<PathBuf, String> {
+    let runner = std::env::current_exe().map_err(|e| e.to_string())?;
+    let path = runner.with_file_name(format!("day_{}{}", day, std::env::consts::EXE_SUFFIX));
+    if path.exists() {
+        Ok(path)
+    } else {
+        Err(format!("{} not found, build it with `cargo build --bin day_{}`", path.display(), day))
+    }
+}
+
+#[derive(Debug, Default)]
+struct Options {
+    verbosity: usize,
+    jobs: Option<usize>,
+}
+
+impl Options {
+    // Splits the runner's flags from the command and its arguments.
+    fn parse(args: impl Iterator<Item=String>) -> Result<(Options, Vec<String>), String> {
+        let mut options = Options::default();
+        let mut rest = vec![];
+        let mut args = args.peekable();
+        while let Some(arg) = args.next() {
+            if arg.len() > 1 && arg.starts_with('-') && arg[1..].chars().all(|c| c == 'v') {
+                options.verbosity += arg.len() - 1;
+            } else if arg == "-j" || arg == "--jobs" || arg.starts_with("--jobs=") {
+                let jobs = match arg.strip_prefix("--jobs=") {
+                    Some(jobs) => jobs.to_string(),
+                    None => args.next().ok_or(format!("missing value for {}", arg))?,
+                };
+                match jobs.parse() {
+                    Ok(jobs) if jobs > 0 => options.jobs = Some(jobs),
+                    _ => return Err(format!("invalid number of jobs: {}", jobs)),
+                }
+            } else {
+                rest.push(arg);
+            }
+        }
+        Ok((options, rest))
+    }
+
+    fn jobs(&self) -> usize {
+        self.jobs.unwrap_or_else(par::jobs)
+    }
+}
+
+// Day binaries read their log filter from AOC_LOG and their thread count from
+// AOC_JOBS. `-v`/`-vv` raise the default level while keeping any per-day
+// entries the user already set.
+fn day_command(day: u32, options: &Options) -> Result<Command, String> {
+    let mut command = Command::new(day_binary(day)?);
+    if let Some(jobs) = options.jobs {
+        command.env("AOC_JOBS", jobs.to_string());
+    }
+    let level = match options.verbosity {
+        0 => None,
+        1 => Some("debug"),
+        _ => Some("trace"),
+    };
+    if let Some(level) = level {
+        let targets = std::env::var("AOC_LOG").unwrap_or_default();
+        command.env("AOC_LOG", format!("{},{}", level, targets));
+    }
+    Ok(command)
+}
+
+fn parse_day(arg: Option<&String>) -> Result<u32, String> {
+    let arg = arg.ok_or("missing <day>")?;
+    match arg.parse() {
+        Ok(day) if (1..=25).contains(&day) => Ok(day),
+        _ => Err(format!("invalid day: {}", arg)),
+    }
+}
+
+fn parse_days(args: &[String]) -> Result<Vec<u32>, String> {
+    if args.is_empty() {
+        Ok((1..=25).collect())
+    } else {
+        args.iter().map(|arg| parse_day(Some(arg))).collect()
+    }
+}
+
+struct DayRun {
+    day: u32,
+    stdout: String,
+    stderr: String,
+    elapsed: Duration,
+    success: bool,
+}
+
+fn run_day(day: u32, options: &Options) -> Result<DayRun, String> {
+    run_day_with(day, options, &[])
+}
+
+fn run_day_with(day: u32, options: &Options, args: &[&str]) -> Result<DayRun, String> {
+    let mut command = day_command(day, options)?;
+    command.args(args);
+    let start = Instant::now();
+    let output = command.output().map_err(|e| e.to_string())?;
+    Ok(DayRun {
+        day,
+        stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+        elapsed: start.elapsed(),
+        success: output.status.success(),
+    })
+}
+
+// Days run concurrently with their output captured, then get printed in the
+// order they were asked for.
+fn run(args: &[String], options: &Options) -> Result<(), String> {
+    let days = parse_days(args)?;
+    let runs = par::map_with_jobs(&days, options.jobs(), |&day| run_day(day, options));
+    let mut failed = vec![];
+    for run in runs {
+        let run = run?;
+        println!("Day {} ({:.2?})", run.day, run.elapsed);
+        print!("{}", run.stdout);
+        eprint!("{}", run.stderr);
+        if !run.success {
+            failed.push(run.day);
+        }
+    }
+    if failed.is_empty() { Ok(()) } else { Err(format!("days {:?} failed", failed)) }
+}
+
+fn lint(args: &[String], options: &Options) -> Result<(), String> {
+    let days = if args.is_empty() { LINT_DAYS.to_vec() } else { parse_days(args)? };
+    if let Some(day) = days.iter().find(|day| !LINT_DAYS.contains(day)) {
+        return Err(format!("day {} has no lint, try one of {:?}", day, LINT_DAYS));
+    }
+    let mut failed = vec![];
+    for day in days {
+        println!("Day {}", day);
+        let run = run_day_with(day, options, &["lint"])?;
+        print!("{}", run.stdout);
+        eprint!("{}", run.stderr);
+        if !run.success {
+            failed.push(day);
+        }
+    }
+    if failed.is_empty() { Ok(()) } else { Err(format!("days {:?} violate their invariants", failed)) }
+}
+
+// Known-good output for a day, kept next to its input as `answers` and
+// written by `accept`.
+fn expected_answers(day: u32) -> Option<String> {
+    std::fs::read_to_string(answers_path(day)).ok()
+}
+
+fn answers_path(day: u32) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!("src/bin/day_{}/answers", day))
+}
+
+fn verification(run: &DayRun) -> &'static str {
+    if !run.success {
+        return "failed";
+    }
+    match expected_answers(run.day) {
+        Some(expected) if expected.trim() == run.stdout.trim() => "verified",
+        Some(_) => "mismatch",
+        None => "unverified",
+    }
+}
+
+fn accept(args: &[String], options: &Options) -> Result<(), String> {
+    let days = parse_days(args)?;
+    for run in par::map_with_jobs(&days, options.jobs(), |&day| run_day(day, options)) {
+        let run = run?;
+        if !run.success {
+            return Err(format!("day {} failed, not saving its output", run.day));
+        }
+        std::fs::write(answers_path(run.day), &run.stdout).map_err(|e| e.to_string())?;
+        println!("Day {}", run.day);
+        print!("{}", run.stdout);
+    }
+    Ok(())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn dashboard(runs: &[Result<DayRun, String>]) -> String {
+    let mut html = String::from(r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Advent of Code 2024</title>
+<style>
+body { background: #0f0f23; color: #cccccc; font-family: monospace; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #333340; padding: 0.3em 0.8em; text-align: left; vertical-align: top; }
+a { color: #009900; }
+.verified { color: #00cc00; } .mismatch, .failed { color: #ff3333; } .unverified { color: #999999; }
+</style>
+</head>
+<body>
+<h1>Advent of Code 2024</h1>
+<p><a href="/refresh">re-run all days</a></p>
+<table>
+<tr><th>day</th><th>output</th><th>time</th><th>status</th></tr>
+"#);
+    for run in runs {
+        match run {
+            Ok(run) => {
+                let status = verification(run);
+                writeln!(
+                    html,
+                    r#"<tr><td>{}</td><td>{}</td><td>{:.2?}</td><td class="{}">{}</td></tr>"#,
+                    run.day,
+                    escape_html(run.stdout.trim()).replace('\n', "<br>"),
+                    run.elapsed,
+                    status,
+                    status,
+                ).unwrap();
+            }
+            Err(err) => {
+                writeln!(html, r#"<tr><td></td><td>{}</td><td></td><td class="failed">failed</td></tr>"#, escape_html(err)).unwrap();
+            }
+        }
+    }
+    let unverified = runs.iter().flatten().filter(|run| verification(run) == "unverified").map(|run| run.day).collect::<Vec<_>>();
+    if !unverified.is_empty() {
+        writeln!(
+            html,
+            r#"<tr><td colspan="4" class="unverified">no answers to check days {} against: save them with <code>aoc accept &lt;day&gt;</code> once adventofcode.com accepts them</td></tr>"#,
+            unverified.iter().map(u32::to_string).collect::<Vec<_>>().join(", "),
+        ).unwrap();
+    }
+    html.push_str("</table>\n");
+    for day in RENDER_DAYS {
+        writeln!(html, r#"<h2>Day {day}</h2>
+<p><a href="/day/{day}.svg"><img src="/day/{day}.svg" alt="day {day}" style="max-width: 100%"></a></p>"#).unwrap();
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn render(day: u32, options: &Options) -> Response {
+    if !RENDER_DAYS.contains(&day) {
+        return Response::not_found();
+    }
+    match run_day_with(day, options, &["render"]) {
+        Ok(run) if run.success => Response::ok("image/svg+xml", run.stdout),
+        Ok(run) => Response::error(run.stderr),
+        Err(err) => Response::error(err),
+    }
+}
+
+fn serve(args: &[String], options: &Options) -> Result<(), String> {
+    let port: u16 = match args.first() {
+        Some(port) => port.parse().map_err(|_| format!("invalid port: {}", port))?,
+        None => 8024,
+    };
+    let listener = TcpListener::bind(("127.0.0.1", port)).map_err(|e| e.to_string())?;
+    println!("serving on http://127.0.0.1:{}", port);
+
+    // Days are only run when the dashboard is first asked for, and again on
+    // /refresh, so reloading the page stays cheap.
+    let days: Vec<u32> = (1..=25).collect();
+    let mut runs: Option<Vec<Result<DayRun, String>>> = None;
+    http::serve(listener, |request: &Request| {
+        let path = request.path.split('?').next().unwrap_or("");
+        match path {
+            "/" | "/refresh" => {
+                if runs.is_none() || path == "/refresh" {
+                    runs = Some(par::map_with_jobs(&days, options.jobs(), |&day| run_day(day, options)));
+                }
+                Response::ok("text/html; charset=utf-8", dashboard(runs.as_ref().unwrap()))
+            }
+            _ => match path.strip_prefix("/day/").and_then(|rest| rest.strip_suffix(".svg")) {
+                Some(day) => day.parse().map_or_else(|_| Response::not_found(), |day| render(day, options)),
+                None => Response::not_found(),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn repl(args: &[String], options: &Options) -> Result<(), String> {
+    let day = parse_day(args.first())?;
+    if !REPL_DAYS.contains(&day) {
+        return Err(format!("day {} has no repl, try one of {:?}", day, REPL_DAYS));
+    }
+    let status = day_command(day, options)?
+        .arg("repl")
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() { Ok(()) } else { Err(format!("day {} exited with {}", day, status)) }
+}
+
+fn main() -> ExitCode {
+    let result = Options::parse(std::env::args().skip(1)).and_then(|(options, args)| {
+        match args.first().map(String::as_str) {
+            Some("run") => run(&args[1..], &options),
+            Some("repl") => repl(&args[1..], &options),
+            Some("lint") => lint(&args[1..], &options),
+            Some("serve") => serve(&args[1..], &options),
+            Some("accept") => accept(&args[1..], &options),
+            None => {
+                println!("Advent of Code 2024!");
+                println!("{}", USAGE);
+                Ok(())
+            }
+            Some(command) => Err(format!("unknown command: {}\n{}", command, USAGE)),
+        }
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
 }
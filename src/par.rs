@@ -0,0 +1,60 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::LazyLock;
+use std::thread;
+
+// Worker count for `map`, from `AOC_JOBS` (set by the runner's `--jobs`) or
+// the number of available cores.
+static JOBS: LazyLock<usize> = LazyLock::new(|| {
+    std::env::var("AOC_JOBS").ok()
+        .and_then(|jobs| jobs.parse().ok())
+        .filter(|&jobs| jobs > 0)
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get()))
+});
+
+pub fn jobs() -> usize {
+    *JOBS
+}
+
+pub fn map<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    map_with_jobs(items, jobs(), f)
+}
+
+// Maps `f` over `items` on up to `jobs` scoped threads. Workers pull the next
+// unclaimed index, so uneven items balance out, and the results are put back
+// in input order so callers see the same output as a serial map.
+pub fn map_with_jobs<T, R, F>(items: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let jobs = jobs.min(items.len());
+    if jobs <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<Option<R>> = items.iter().map(|_| None).collect();
+    thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs).map(|_| scope.spawn(|| {
+            let mut done = vec![];
+            loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= items.len() {
+                    break done;
+                }
+                done.push((i, f(&items[i])));
+            }
+        })).collect();
+        for worker in workers {
+            for (i, result) in worker.join().unwrap() {
+                results[i] = Some(result);
+            }
+        }
+    });
+    results.into_iter().map(Option::unwrap).collect()
+}
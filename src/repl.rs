@@ -0,0 +1,129 @@
+use std::io::{BufRead, Write};
+
+type Handler<S> = fn(&mut S, &[&str]) -> Result<String, String>;
+
+struct Command<S> {
+    name: &'static str,
+    usage: &'static str,
+    help: &'static str,
+    handler: Handler<S>,
+}
+
+// A small line-based shell over some parsed puzzle state. Each day registers
+// the commands that make sense for it; `help`, `history`, `!n`, `!!`, `reset`
+// and `quit` come for free.
+pub struct Repl<S: Clone> {
+    prompt: String,
+    initial: S,
+    state: S,
+    commands: Vec<Command<S>>,
+    history: Vec<String>,
+}
+
+impl<S: Clone> Repl<S> {
+    pub fn new(prompt: &str, state: S) -> Self {
+        Repl {
+            prompt: prompt.to_string(),
+            initial: state.clone(),
+            state,
+            commands: vec![],
+            history: vec![],
+        }
+    }
+
+    pub fn command(mut self, name: &'static str, usage: &'static str, help: &'static str, handler: Handler<S>) -> Self {
+        self.commands.push(Command { name, usage, help, handler });
+        self
+    }
+
+    pub fn run(&mut self) {
+        let stdin = std::io::stdin();
+        let stdout = std::io::stdout();
+        self.run_with(stdin.lock(), stdout.lock());
+    }
+
+    pub fn run_with<R: BufRead, W: Write>(&mut self, mut reader: R, mut writer: W) {
+        let mut line = String::new();
+        loop {
+            write!(writer, "{}> ", self.prompt).unwrap();
+            writer.flush().unwrap();
+            line.clear();
+            if reader.read_line(&mut line).unwrap() == 0 {
+                writeln!(writer).unwrap();
+                break;
+            }
+            let Some(entry) = self.expand_history(line.trim()) else {
+                writeln!(writer, "error: no such history entry: {}", line.trim()).unwrap();
+                continue;
+            };
+            if entry.is_empty() {
+                continue;
+            }
+            if entry != line.trim() {
+                writeln!(writer, "{}", entry).unwrap();
+            }
+            self.history.push(entry.clone());
+            let words: Vec<&str> = entry.split_whitespace().collect();
+            match self.eval(&words) {
+                Some(Ok(out)) if out.is_empty() => {}
+                Some(Ok(out)) => writeln!(writer, "{}", out.trim_end()).unwrap(),
+                Some(Err(err)) => writeln!(writer, "error: {}", err).unwrap(),
+                None => break,
+            }
+        }
+    }
+
+    fn expand_history(&self, line: &str) -> Option<String> {
+        if line == "!!" {
+            self.history.last().cloned()
+        } else if let Some(n) = line.strip_prefix('!') {
+            let n: usize = n.parse().ok()?;
+            self.history.get(n.checked_sub(1)?).cloned()
+        } else {
+            Some(line.to_string())
+        }
+    }
+
+    // Returns `None` when the session should end.
+    fn eval(&mut self, words: &[&str]) -> Option<Result<String, String>> {
+        let (name, args) = words.split_first()?;
+        Some(match *name {
+            "quit" | "exit" => return None,
+            "help" => Ok(self.help()),
+            "history" => Ok(self.history.iter().enumerate()
+                .map(|(i, entry)| format!("{:>4}  {}", i + 1, entry))
+                .collect::<Vec<_>>()
+                .join("\n")),
+            "reset" => {
+                self.state = self.initial.clone();
+                Ok("state reset to the parsed input".to_string())
+            }
+            _ => match self.commands.iter().find(|command| command.name == *name) {
+                Some(command) => (command.handler)(&mut self.state, args),
+                None => Err(format!("unknown command '{}', try 'help'", name)),
+            }
+        })
+    }
+
+    fn help(&self) -> String {
+        let builtins = [
+            ("help", "", "list commands"),
+            ("history", "", "list previous commands"),
+            ("!n / !!", "", "re-run history entry n / the last command"),
+            ("reset", "", "discard changes and reload the parsed input"),
+            ("quit", "", "leave the repl"),
+        ];
+        self.commands.iter()
+            .map(|command| (command.name, command.usage, command.help))
+            .chain(builtins)
+            .map(|(name, usage, help)| format!("  {:<24} {}", format!("{} {}", name, usage), help))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// Parses the `i`th argument of a repl command, naming it in the error.
+pub fn arg<T: std::str::FromStr>(args: &[&str], i: usize, name: &str) -> Result<T, String> {
+    let raw = args.get(i).ok_or(format!("missing argument <{}>", name))?;
+    raw.parse().map_err(|_| format!("invalid <{}>: {}", name, raw))
+}
@@ -0,0 +1,43 @@
+use std::fmt::Write;
+
+const CELL: usize = 8;
+const BACKGROUND: &str = "#0f0f23";
+
+// Renders a `width` x `height` grid as an SVG, one square per cell. `color`
+// returns the fill for a cell, or `None` to leave it as background.
+pub fn grid<F: Fn(usize, usize) -> Option<String>>(width: usize, height: usize, color: F) -> String {
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+        w = width * CELL,
+        h = height * CELL,
+    ).unwrap();
+    writeln!(svg, r#"<rect width="100%" height="100%" fill="{}"/>"#, BACKGROUND).unwrap();
+    for y in 0..height {
+        for x in 0..width {
+            if let Some(fill) = color(x, y) {
+                writeln!(
+                    svg,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    x * CELL, y * CELL, CELL, CELL, fill,
+                ).unwrap();
+            }
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+// Renders a text grid such as a day's `Display` output, colouring by character.
+pub fn text_grid<F: Fn(char) -> Option<String>>(text: &str, color: F) -> String {
+    let rows: Vec<Vec<char>> = text.lines().map(|line| line.chars().collect()).collect();
+    let width = rows.iter().map(|row| row.len()).max().unwrap_or(0);
+    grid(width, rows.len(), |x, y| rows[y].get(x).and_then(|c| color(*c)))
+}
+
+// A distinguishable colour for the `i`th thing, e.g. a file id or a region.
+pub fn palette(i: usize) -> String {
+    // stepping by the golden angle keeps neighbouring ids far apart in hue
+    format!("hsl({}, 70%, 55%)", (i as f64 * 137.508) % 360.0)
+}